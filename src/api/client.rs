@@ -1,82 +1,249 @@
 #![allow(dead_code)]
 
 use anyhow::{Context, Result, anyhow};
+use chrono::Utc;
+use reqwest::header::{ETAG, IF_NONE_MATCH};
 use reqwest::{Client, StatusCode};
+use std::collections::HashSet;
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use tracing::{debug, error, warn};
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, RwLock};
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
 
+use crate::api::error::TwitchApiError;
 use crate::api::models::*;
 use crate::api::oauth::OAuthFlow;
-use crate::config::Config;
+use crate::api::rate_limit::{self, RateLimit};
+use crate::config::{AuthMode, Config, CredentialStore, Tokens, TwitchConfig};
 
-const TWITCH_API_BASE: &str = "https://api.twitch.tv/helix";
-const TWITCH_VALIDATE_URL: &str = "https://id.twitch.tv/oauth2/validate";
+const TOKEN_RENEWAL_MARGIN: Duration = Duration::from_secs(5 * 60);
+const RATE_LIMIT_RETRY_DELAY: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_PAGES: usize = 50;
+
+#[derive(Debug, Default)]
+struct TokenState {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    expires_at: Option<Instant>,
+    // Twitch budgets requests per token, so a new token starts unknown
+    rate_limit: Option<RateLimit>,
+}
+
+// Without timeouts a request on a dropped connection would stall the poll loop indefinitely
+pub fn http_client() -> Client {
+    Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .build()
+        .unwrap_or_else(|e| {
+            warn!("Failed to configure HTTP client timeouts: {}", e);
+            Client::new()
+        })
+}
 
 pub struct TwitchClient {
     client: Client,
     client_id: String,
-    access_token: Option<String>,
+    api_base_url: String,
+    oauth_base_url: String,
+    tokens: RwLock<TokenState>,
+    // Held while renewing, as Twitch invalidates the refresh token on every use
+    refresh_lock: Mutex<()>,
+    credentials: Arc<CredentialStore>,
+    account: String,
     config: Arc<RwLock<Config>>,
 }
 
 impl TwitchClient {
-    pub fn new(client_id: String, config: Arc<RwLock<Config>>) -> Self {
+    pub fn new(
+        twitch_config: &TwitchConfig,
+        config: Arc<RwLock<Config>>,
+        credentials: Arc<CredentialStore>,
+        account: &str,
+    ) -> Self {
         Self {
-            client: Client::new(),
-            client_id,
-            access_token: None,
+            client: http_client(),
+            client_id: twitch_config.client_id.clone(),
+            api_base_url: twitch_config.api_base_url.trim_end_matches('/').to_string(),
+            oauth_base_url: twitch_config
+                .oauth_base_url
+                .trim_end_matches('/')
+                .to_string(),
+            tokens: RwLock::new(TokenState::default()),
+            refresh_lock: Mutex::new(()),
+            credentials,
+            account: account.to_string(),
             config,
         }
     }
 
     pub fn set_access_token(&mut self, token: String) {
-        self.access_token = Some(token);
+        let tokens = self.tokens.get_mut();
+        tokens.access_token = Some(token);
+        tokens.rate_limit = None;
     }
 
-    pub async fn load_token_from_config(&mut self) -> Result<()> {
-        let config = self.config.read().await;
-        if let Some(ref token) = config.twitch.access_token {
-            self.access_token = Some(token.clone());
-            debug!("Loaded access token from config");
+    pub async fn rate_limit(&self) -> Option<RateLimit> {
+        self.tokens.read().await.rate_limit
+    }
+
+    pub fn credentials(&self) -> &CredentialStore {
+        &self.credentials
+    }
+
+    pub fn account(&self) -> &str {
+        &self.account
+    }
+
+    pub async fn load_tokens(&mut self) -> Result<()> {
+        let stored = self
+            .credentials
+            .load(&self.account)
+            .await
+            .with_context(|| format!("Failed to load tokens from {}", self.credentials.name()))?;
+
+        let tokens = self.tokens.get_mut();
+        if stored.access_token.is_some() {
+            debug!("Loaded access token from {}", self.credentials.name());
         }
+        tokens.access_token = stored.access_token;
+        tokens.refresh_token = stored.refresh_token;
+        tokens.rate_limit = None;
         Ok(())
     }
 
+    pub async fn has_access_token(&self) -> bool {
+        self.tokens.read().await.access_token.is_some()
+    }
+
     pub async fn authenticate(&mut self) -> Result<()> {
-        let mut oauth_flow = OAuthFlow::new(self.client_id.clone());
-        let token_response = oauth_flow.authenticate().await?;
+        let auth_mode = self.config.read().await.twitch.auth_mode;
+        let mut oauth_flow = OAuthFlow::new(self.client_id.clone(), self.oauth_base_url.clone());
+        let token_response = match auth_mode {
+            AuthMode::Implicit => oauth_flow.authenticate().await?,
+            AuthMode::DeviceCode => oauth_flow.authenticate_device().await?,
+        };
+
+        self.store_tokens(token_response).await
+    }
 
-        self.access_token = Some(token_response.access_token.clone());
+    pub async fn has_refresh_token(&self) -> bool {
+        self.tokens.read().await.refresh_token.is_some()
+    }
 
-        {
-            let mut config = self.config.write().await;
-            config.twitch.access_token = Some(token_response.access_token);
-            config
-                .save_default()
-                .await
-                .context("Failed to save token to config")?;
+    pub async fn refresh_access_token(&self) -> Result<()> {
+        let rejected = self.tokens.read().await.access_token.clone();
+        let _renewing = self.refresh_lock.lock().await;
+
+        // Another request or process sharing these credentials may have renewed them meanwhile
+        let stored = self
+            .credentials
+            .load(&self.account)
+            .await
+            .with_context(|| format!("Failed to load tokens from {}", self.credentials.name()))?;
+        if stored.access_token.is_some() && stored.access_token != rejected {
+            debug!("Access token was already renewed, using the stored one");
+            let mut tokens = self.tokens.write().await;
+            tokens.access_token = stored.access_token;
+            tokens.refresh_token = stored.refresh_token;
+            tokens.expires_at = None;
+            tokens.rate_limit = None;
+            return Ok(());
         }
 
+        let refresh_token = match stored.refresh_token {
+            Some(refresh_token) => refresh_token,
+            None => self
+                .tokens
+                .read()
+                .await
+                .refresh_token
+                .clone()
+                .ok_or_else(|| anyhow!("No refresh token available"))?,
+        };
+
+        let oauth_flow = OAuthFlow::new(self.client_id.clone(), self.oauth_base_url.clone());
+        let token_response = oauth_flow
+            .refresh(&refresh_token)
+            .await
+            .context("Failed to renew access token")?;
+
+        self.store_tokens(token_response).await?;
+        info!("Access token renewed using refresh token");
+        Ok(())
+    }
+
+    async fn store_tokens(&self, token_response: TokenResponse) -> Result<()> {
+        let stored = {
+            let mut tokens = self.tokens.write().await;
+            tokens.access_token = Some(token_response.access_token.clone());
+            tokens.rate_limit = None;
+            if let Some(ref refresh_token) = token_response.refresh_token {
+                tokens.refresh_token = Some(refresh_token.clone());
+            }
+            tokens.expires_at = token_response
+                .expires_in
+                .map(|secs| Instant::now() + Duration::from_secs(secs));
+
+            Tokens {
+                access_token: tokens.access_token.clone(),
+                refresh_token: tokens.refresh_token.clone(),
+            }
+        };
+
+        self.credentials
+            .save(&self.account, &stored)
+            .await
+            .with_context(|| format!("Failed to save tokens to {}", self.credentials.name()))
+    }
+
+    async fn renew_token_if_expiring(&self) -> Result<()> {
+        let expiring = {
+            let tokens = self.tokens.read().await;
+            tokens.refresh_token.is_some()
+                && tokens.expires_at.is_some_and(|at| {
+                    at.saturating_duration_since(Instant::now()) < TOKEN_RENEWAL_MARGIN
+                })
+        };
+
+        if expiring {
+            debug!("Access token is about to expire, renewing");
+            self.refresh_access_token().await?;
+        }
         Ok(())
     }
 
     pub async fn validate_token(&self) -> Result<TokenValidation> {
         let token = self
+            .tokens
+            .read()
+            .await
             .access_token
-            .as_ref()
+            .clone()
             .ok_or_else(|| anyhow!("No access token available"))?;
 
         let response = self
             .client
-            .get(TWITCH_VALIDATE_URL)
+            .get(format!("{}/validate", self.oauth_base_url))
             .header("Authorization", format!("OAuth {token}"))
             .send()
             .await
+            .map_err(TwitchApiError::Network)
             .context("Failed to validate token")?;
 
-        if !response.status().is_success() {
-            return Err(anyhow!("Token validation failed: {}", response.status()));
+        match response.status() {
+            status if status.is_success() => {}
+            StatusCode::UNAUTHORIZED => {
+                return Err(TwitchApiError::Unauthorized).context("Token validation failed");
+            }
+            status => {
+                let body = response.text().await.unwrap_or_default();
+                return Err(TwitchApiError::Server { status, body })
+                    .context("Token validation failed");
+            }
         }
 
         let validation: TokenValidation = response
@@ -84,7 +251,13 @@ impl TwitchClient {
             .await
             .context("Failed to parse token validation response")?;
 
-        debug!("Token validated for user: {}", validation.login);
+        self.tokens.write().await.expires_at =
+            Some(Instant::now() + Duration::from_secs(validation.expires_in));
+
+        debug!(
+            "Token validated for user: {} (expires in {}s)",
+            validation.login, validation.expires_in
+        );
         Ok(validation)
     }
 
@@ -97,6 +270,7 @@ impl TwitchClient {
         let users: TwitchResponse<User> = response
             .json()
             .await
+            .map_err(TwitchApiError::Decode)
             .context("Failed to parse user response")?;
 
         users
@@ -109,6 +283,7 @@ impl TwitchClient {
     pub async fn get_followed_channels(&self, user_id: &str) -> Result<Vec<FollowedChannel>> {
         let mut all_channels = Vec::new();
         let mut cursor: Option<String> = None;
+        let mut seen_cursors = HashSet::new();
 
         loop {
             let mut params = vec![("user_id", user_id), ("first", "100")];
@@ -125,11 +300,16 @@ impl TwitchClient {
             let channels_response: TwitchResponse<FollowedChannel> = response
                 .json()
                 .await
+                .map_err(TwitchApiError::Decode)
                 .context("Failed to parse followed channels response")?;
 
             all_channels.extend(channels_response.data);
 
-            cursor = channels_response.pagination.and_then(|p| p.cursor);
+            cursor = next_cursor(
+                channels_response.pagination.and_then(|p| p.cursor),
+                &mut seen_cursors,
+                "followed channels",
+            );
 
             if cursor.is_none() {
                 break;
@@ -141,20 +321,49 @@ impl TwitchClient {
     }
 
     pub async fn get_followed_streams(&self, user_id: &str) -> Result<Vec<Stream>> {
-        let params = vec![("user_id", user_id), ("first", "100")];
+        let mut all_streams: Vec<Stream> = Vec::new();
+        let mut seen_ids = HashSet::new();
+        let mut cursor: Option<String> = None;
+        let mut seen_cursors = HashSet::new();
 
-        let response = self
-            .make_api_request("streams/followed", &params)
-            .await
-            .context("Failed to get followed streams")?;
+        loop {
+            let mut params = vec![("user_id", user_id), ("first", "100")];
 
-        let streams_response: TwitchResponse<Stream> = response
-            .json()
-            .await
-            .context("Failed to parse followed streams response")?;
+            if let Some(ref cursor_val) = cursor {
+                params.push(("after", cursor_val));
+            }
 
-        debug!("Retrieved {} live streams", streams_response.data.len());
-        Ok(streams_response.data)
+            let response = self
+                .make_api_request("streams/followed", &params)
+                .await
+                .context("Failed to get followed streams")?;
+
+            let streams_response: TwitchResponse<Stream> = response
+                .json()
+                .await
+                .map_err(TwitchApiError::Decode)
+                .context("Failed to parse followed streams response")?;
+
+            // Streams are ordered by viewer count, so a stream can shift onto the next page
+            for stream in streams_response.data {
+                if seen_ids.insert(stream.id.clone()) {
+                    all_streams.push(stream);
+                }
+            }
+
+            cursor = next_cursor(
+                streams_response.pagination.and_then(|p| p.cursor),
+                &mut seen_cursors,
+                "followed streams",
+            );
+
+            if cursor.is_none() {
+                break;
+            }
+        }
+
+        debug!("Retrieved {} live streams", all_streams.len());
+        Ok(all_streams)
     }
 
     pub async fn get_streams_by_user_ids(&self, user_ids: &[String]) -> Result<Vec<Stream>> {
@@ -175,6 +384,7 @@ impl TwitchClient {
         let streams_response: TwitchResponse<Stream> = response
             .json()
             .await
+            .map_err(TwitchApiError::Decode)
             .context("Failed to parse streams response")?;
 
         debug!(
@@ -184,6 +394,21 @@ impl TwitchClient {
         Ok(streams_response.data)
     }
 
+    pub async fn get_user_by_login(&self, login: &str) -> Result<Option<User>> {
+        let response = self
+            .make_api_request("users", &[("login", login)])
+            .await
+            .with_context(|| format!("Failed to look up channel {login}"))?;
+
+        let users: TwitchResponse<User> = response
+            .json()
+            .await
+            .map_err(TwitchApiError::Decode)
+            .context("Failed to parse users response")?;
+
+        Ok(users.data.into_iter().next())
+    }
+
     pub async fn get_users_by_ids(&self, user_ids: &[String]) -> Result<Vec<User>> {
         if user_ids.is_empty() {
             return Ok(Vec::new());
@@ -202,6 +427,7 @@ impl TwitchClient {
         let users_response: TwitchResponse<User> = response
             .json()
             .await
+            .map_err(TwitchApiError::Decode)
             .context("Failed to parse users response")?;
 
         debug!("Retrieved {} users by IDs", users_response.data.len());
@@ -209,36 +435,55 @@ impl TwitchClient {
     }
 
     pub async fn download_profile_image(&self, url: &str) -> Result<Vec<u8>> {
-        let response = self
-            .client
-            .get(url)
-            .send()
-            .await
-            .context("Failed to download profile image")?;
+        match self.download_image(url, None).await? {
+            ImageDownload::Modified { bytes, .. } => Ok(bytes),
+            ImageDownload::NotModified => Err(anyhow!("Unexpected 304 for unconditional request")),
+        }
+    }
+
+    pub async fn download_image(&self, url: &str, etag: Option<&str>) -> Result<ImageDownload> {
+        let mut request = self.client.get(url);
+        if let Some(etag) = etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+
+        let response = request.send().await.context("Failed to download image")?;
+
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(ImageDownload::NotModified);
+        }
 
         if !response.status().is_success() {
             return Err(anyhow!("Failed to download image: {}", response.status()));
         }
 
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+
         let bytes = response
             .bytes()
             .await
             .context("Failed to read image bytes")?;
 
-        Ok(bytes.to_vec())
+        Ok(ImageDownload::Modified {
+            bytes: bytes.to_vec(),
+            etag,
+        })
     }
 
     async fn make_api_request(
         &self,
         endpoint: &str,
         params: &[(&str, &str)],
-    ) -> Result<reqwest::Response> {
-        let token = self
-            .access_token
-            .as_ref()
-            .ok_or_else(|| anyhow!("No access token available for API request"))?;
+    ) -> Result<reqwest::Response, TwitchApiError> {
+        if let Err(e) = self.renew_token_if_expiring().await {
+            warn!("Proactive token renewal failed: {:#}", e);
+        }
 
-        let mut url = format!("{TWITCH_API_BASE}/{endpoint}");
+        let mut url = format!("{}/{endpoint}", self.api_base_url);
 
         if !params.is_empty() {
             url.push('?');
@@ -252,30 +497,409 @@ impl TwitchClient {
 
         debug!("Making API request: {}", url);
 
-        let response = self
-            .client
-            .get(&url)
-            .header("Client-ID", &self.client_id)
-            .header("Authorization", format!("Bearer {token}"))
-            .send()
-            .await
-            .context("Failed to make API request")?;
+        let mut response = self.send_api_request(&url).await?;
+
+        if response.status() == StatusCode::UNAUTHORIZED && self.has_refresh_token().await {
+            warn!("API request unauthorized, renewing access token and retrying");
+            match self.refresh_access_token().await {
+                Ok(()) => response = self.send_api_request(&url).await?,
+                Err(e) => error!("Token renewal failed: {:#}", e),
+            }
+        }
+
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            let wait = rate_limit::reset_from_headers(response.headers())
+                .map(|reset| rate_limit::until(reset, Utc::now()))
+                .unwrap_or(RATE_LIMIT_RETRY_DELAY);
+            warn!("API request rate limited (429), retrying in {:?}", wait);
+            sleep(wait).await;
+            response = self.send_api_request(&url).await?;
+        }
 
         match response.status() {
             StatusCode::OK => Ok(response),
             StatusCode::UNAUTHORIZED => {
                 error!("API request failed: Unauthorized (401)");
-                Err(anyhow!("Authentication failed - token may be expired"))
+                Err(TwitchApiError::Unauthorized)
             }
             StatusCode::TOO_MANY_REQUESTS => {
                 warn!("API request failed: Rate limit exceeded (429)");
-                Err(anyhow!("Rate limit exceeded"))
+                Err(TwitchApiError::rate_limited(response.headers()))
             }
             status => {
                 error!("API request failed with status: {}", status);
-                let error_text = response.text().await.unwrap_or_default();
-                Err(anyhow!("API request failed ({}): {}", status, error_text))
+                let body = response.text().await.unwrap_or_default();
+                Err(TwitchApiError::Server { status, body })
             }
         }
     }
+
+    async fn send_api_request(&self, url: &str) -> Result<reqwest::Response, TwitchApiError> {
+        let Some(token) = self.tokens.read().await.access_token.clone() else {
+            warn!("No access token available for API request");
+            return Err(TwitchApiError::Unauthorized);
+        };
+
+        self.wait_for_budget().await;
+
+        let response = self
+            .client
+            .get(url)
+            .header("Client-ID", &self.client_id)
+            .header("Authorization", format!("Bearer {token}"))
+            .send()
+            .await
+            .map_err(TwitchApiError::Network)?;
+
+        if let Some(reported) = RateLimit::from_headers(response.headers()) {
+            let mut tokens = self.tokens.write().await;
+            let budget = match tokens.rate_limit {
+                Some(known) => known.updated(reported),
+                None => reported,
+            };
+            debug!("Rate limit budget: {}", budget);
+            tokens.rate_limit = Some(budget);
+        }
+
+        Ok(response)
+    }
+
+    async fn wait_for_budget(&self) {
+        let delay = {
+            let mut tokens = self.tokens.write().await;
+            let Some(budget) = tokens.rate_limit.as_mut() else {
+                return;
+            };
+
+            let delay = budget.delay(Utc::now());
+            if let Some(delay) = delay {
+                warn!(
+                    "Rate limit budget low ({}), delaying request by {:?}",
+                    budget, delay
+                );
+            }
+            budget.spend();
+            delay
+        };
+
+        if let Some(delay) = delay {
+            sleep(delay).await;
+        }
+    }
+}
+
+// A cursor that repeats, or pages that never end, would keep a poll requesting forever
+fn next_cursor(
+    cursor: Option<String>,
+    seen: &mut HashSet<String>,
+    listing: &str,
+) -> Option<String> {
+    let cursor = cursor?;
+    if seen.len() + 1 >= MAX_PAGES {
+        warn!("Stopped listing {} after {} pages", listing, MAX_PAGES);
+        return None;
+    }
+    if !seen.insert(cursor.clone()) {
+        warn!("Stopped listing {}, Twitch repeated a page cursor", listing);
+        return None;
+    }
+    Some(cursor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_ACCOUNT;
+    use crate::testing::FakeTwitch;
+    use crate::testing::fake_twitch::{ACCESS_TOKEN, REFRESH_TOKEN, channel, stream, user};
+
+    async fn client_with(fake: &FakeTwitch, credentials: Arc<CredentialStore>) -> TwitchClient {
+        let config = fake.config();
+        let mut client = TwitchClient::new(
+            &config.twitch,
+            Arc::new(RwLock::new(config.clone())),
+            credentials,
+            DEFAULT_ACCOUNT,
+        );
+        client.load_tokens().await.unwrap();
+        client
+    }
+
+    async fn client_for(fake: &FakeTwitch) -> TwitchClient {
+        client_with(fake, Arc::new(fake.credentials().await)).await
+    }
+
+    async fn refreshable_credentials(fake: &FakeTwitch) -> Arc<CredentialStore> {
+        let credentials = fake
+            .credentials_with(Tokens {
+                access_token: Some(ACCESS_TOKEN.to_string()),
+                refresh_token: Some(REFRESH_TOKEN.to_string()),
+            })
+            .await;
+        Arc::new(credentials)
+    }
+
+    async fn refreshable_client_for(fake: &FakeTwitch) -> TwitchClient {
+        client_with(fake, refreshable_credentials(fake).await).await
+    }
+
+    #[tokio::test]
+    async fn validates_token_against_configured_endpoint() {
+        let fake = FakeTwitch::start();
+        let client = client_for(&fake).await;
+
+        let validation = client.validate_token().await.unwrap();
+        assert_eq!(validation.login, "viewer");
+        assert_eq!(validation.expires_in, 3600);
+
+        fake.state().access_token = "rotated".to_string();
+        assert!(client.validate_token().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn get_user_returns_authenticated_user() {
+        let fake = FakeTwitch::start();
+        let client = client_for(&fake).await;
+
+        let user = client.get_user().await.unwrap();
+        assert_eq!(user.id, "1000");
+        assert_eq!(user.login, "viewer");
+    }
+
+    #[tokio::test]
+    async fn followed_streams_are_paginated_and_deduplicated() {
+        let fake = FakeTwitch::start();
+        {
+            let mut state = fake.state();
+            state.page_size = 2;
+            state.page_overlap = 1;
+            state.streams = (0..5)
+                .map(|i| {
+                    stream(
+                        &format!("s{i}"),
+                        &format!("u{i}"),
+                        &format!("user{i}"),
+                        100 - i,
+                    )
+                })
+                .collect();
+        }
+        let client = client_for(&fake).await;
+
+        let streams = client.get_followed_streams("1000").await.unwrap();
+        let ids: Vec<&str> = streams.iter().map(|s| s.id.as_str()).collect();
+        assert_eq!(ids, ["s0", "s1", "s2", "s3", "s4"]);
+
+        let pages = fake
+            .state()
+            .requests
+            .iter()
+            .filter(|r| r.starts_with("/helix/streams/followed"))
+            .count();
+        assert!(pages > 2);
+    }
+
+    #[tokio::test]
+    async fn repeated_cursor_ends_pagination() {
+        let fake = FakeTwitch::start();
+        {
+            let mut state = fake.state();
+            state.page_size = 2;
+            state.repeat_cursor = true;
+            state.streams = (0..5)
+                .map(|i| stream(&format!("s{i}"), &format!("u{i}"), &format!("user{i}"), 10))
+                .collect();
+            state.channels = (0..5)
+                .map(|i| channel(&format!("u{i}"), &format!("user{i}")))
+                .collect();
+        }
+        let client = client_for(&fake).await;
+
+        let streams = client.get_followed_streams("1000").await.unwrap();
+        assert_eq!(streams.len(), 2);
+        let channels = client.get_followed_channels("1000").await.unwrap();
+        assert_eq!(channels.len(), 4);
+        assert_eq!(fake.state().requests.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn pagination_stops_at_page_limit() {
+        let fake = FakeTwitch::start();
+        {
+            let mut state = fake.state();
+            state.page_size = 1;
+            state.streams = (0..MAX_PAGES + 10)
+                .map(|i| stream(&format!("s{i}"), &format!("u{i}"), &format!("user{i}"), 10))
+                .collect();
+        }
+        let client = client_for(&fake).await;
+
+        let streams = client.get_followed_streams("1000").await.unwrap();
+        assert_eq!(streams.len(), MAX_PAGES);
+    }
+
+    #[tokio::test]
+    async fn followed_channels_are_paginated() {
+        let fake = FakeTwitch::start();
+        {
+            let mut state = fake.state();
+            state.page_size = 3;
+            state.channels = (0..7)
+                .map(|i| channel(&format!("u{i}"), &format!("user{i}")))
+                .collect();
+        }
+        let client = client_for(&fake).await;
+
+        let channels = client.get_followed_channels("1000").await.unwrap();
+        assert_eq!(channels.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn rejected_token_fails_api_request() {
+        let fake = FakeTwitch::start();
+        let client = client_for(&fake).await;
+        fake.state().access_token = "rotated".to_string();
+
+        let err = client.get_user().await.unwrap_err();
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn unexpected_status_surfaces_server_error() {
+        let fake = FakeTwitch::start();
+        let client = client_for(&fake).await;
+
+        let err = client
+            .get_streams_by_user_ids(&["1".to_string()])
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::Server { status, .. }) if *status == StatusCode::NOT_FOUND
+        ));
+    }
+
+    #[tokio::test]
+    async fn channel_is_looked_up_by_login() {
+        let fake = FakeTwitch::start();
+        fake.state().users.push(user("12826", "twitch"));
+        let client = client_for(&fake).await;
+
+        let found = client.get_user_by_login("twitch").await.unwrap().unwrap();
+        assert_eq!(found.id, "12826");
+        assert!(client.get_user_by_login("nobody").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn rate_limit_budget_is_tracked_from_headers() {
+        let fake = FakeTwitch::start();
+        let client = client_for(&fake).await;
+        assert!(client.rate_limit().await.is_none());
+
+        client.get_user().await.unwrap();
+        client.get_user().await.unwrap();
+
+        let budget = client.rate_limit().await.unwrap();
+        assert_eq!(budget.limit, 800);
+        assert_eq!(budget.remaining, 798);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_request_is_retried_after_reset() {
+        let fake = FakeTwitch::start();
+        let client = client_for(&fake).await;
+        fake.state().throttled_requests = 1;
+
+        let user = client.get_user().await.unwrap();
+        assert_eq!(user.login, "viewer");
+        assert_eq!(fake.state().requests.len(), 2);
+
+        fake.state().throttled_requests = 2;
+        let err = client.get_user().await.unwrap_err();
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::RateLimited { reset: Some(_) })
+        ));
+    }
+
+    #[tokio::test]
+    async fn unauthorized_request_renews_token_and_retries() {
+        let fake = FakeTwitch::start();
+        let client = refreshable_client_for(&fake).await;
+        fake.state().access_token = "revoked-on-server".to_string();
+
+        let user = client.get_user().await.unwrap();
+        assert_eq!(user.login, "viewer");
+        assert_eq!(fake.state().refreshes, 1);
+
+        let stored = client.credentials().load(DEFAULT_ACCOUNT).await.unwrap();
+        assert_eq!(
+            stored.access_token.as_deref(),
+            Some("refreshed-access-token-1")
+        );
+        assert_eq!(
+            stored.refresh_token.as_deref(),
+            Some("refreshed-refresh-token-1")
+        );
+    }
+
+    #[tokio::test]
+    async fn concurrent_unauthorized_requests_renew_token_once() {
+        let fake = FakeTwitch::start();
+        let client = refreshable_client_for(&fake).await;
+        fake.state().access_token = "revoked-on-server".to_string();
+
+        let (first, second) = tokio::join!(client.get_user(), client.get_user());
+        assert_eq!(first.unwrap().login, "viewer");
+        assert_eq!(second.unwrap().login, "viewer");
+        assert_eq!(fake.state().refreshes, 1);
+    }
+
+    #[tokio::test]
+    async fn token_renewed_by_another_client_is_reused() {
+        let fake = FakeTwitch::start();
+        let credentials = refreshable_credentials(&fake).await;
+        let first = client_with(&fake, credentials.clone()).await;
+        let second = client_with(&fake, credentials).await;
+        fake.state().access_token = "revoked-on-server".to_string();
+
+        first.get_user().await.unwrap();
+        let user = second.get_user().await.unwrap();
+        assert_eq!(user.login, "viewer");
+        assert_eq!(fake.state().refreshes, 1);
+    }
+
+    #[tokio::test]
+    async fn expiring_token_is_renewed_before_request() {
+        let fake = FakeTwitch::start();
+        fake.state().expires_in = 60;
+        let client = refreshable_client_for(&fake).await;
+
+        client.validate_token().await.unwrap();
+        client.get_user().await.unwrap();
+        assert_eq!(fake.state().refreshes, 1);
+
+        client.get_user().await.unwrap();
+        assert_eq!(fake.state().refreshes, 1);
+    }
+
+    #[tokio::test]
+    async fn failed_renewal_surfaces_unauthorized() {
+        let fake = FakeTwitch::start();
+        let client = refreshable_client_for(&fake).await;
+        {
+            let mut state = fake.state();
+            state.access_token = "revoked-on-server".to_string();
+            state.refresh_token = "revoked-refresh-token".to_string();
+        }
+
+        let err = client.get_user().await.unwrap_err();
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::Unauthorized)
+        ));
+    }
 }
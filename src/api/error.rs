@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use reqwest::StatusCode;
+use reqwest::header::HeaderMap;
+use thiserror::Error;
+
+use crate::api::rate_limit;
+
+#[derive(Debug, Error)]
+pub enum TwitchApiError {
+    #[error("Authentication failed - token may be expired")]
+    Unauthorized,
+
+    #[error("Rate limit exceeded")]
+    RateLimited { reset: Option<DateTime<Utc>> },
+
+    #[error("Network error: {0}")]
+    Network(#[source] reqwest::Error),
+
+    #[error("API request failed ({status}): {body}")]
+    Server { status: StatusCode, body: String },
+
+    #[error("Failed to decode API response: {0}")]
+    Decode(#[source] reqwest::Error),
+}
+
+impl TwitchApiError {
+    // Callers wrap API errors in anyhow context, so look through the whole chain
+    pub fn find(err: &anyhow::Error) -> Option<&Self> {
+        err.chain().find_map(|cause| cause.downcast_ref::<Self>())
+    }
+
+    // Failures that may clear up on their own, as opposed to ones needing the user
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Network(_) | Self::RateLimited { .. } => true,
+            Self::Server { status, .. } => status.is_server_error(),
+            Self::Unauthorized | Self::Decode(_) => false,
+        }
+    }
+
+    pub fn rate_limited(headers: &HeaderMap) -> Self {
+        Self::RateLimited {
+            reset: rate_limit::reset_from_headers(headers),
+        }
+    }
+}
+
+pub fn is_transient(err: &anyhow::Error) -> bool {
+    TwitchApiError::find(err).is_some_and(TwitchApiError::is_transient)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn variant_is_found_behind_context() {
+        let err = Err::<(), _>(TwitchApiError::Unauthorized)
+            .context("Failed to get user info")
+            .context("Failed to update streams")
+            .unwrap_err();
+
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::Unauthorized)
+        ));
+        assert!(TwitchApiError::find(&anyhow::anyhow!("unrelated")).is_none());
+    }
+
+    #[test]
+    fn rate_limit_reset_is_read_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Ratelimit-Reset", HeaderValue::from_static("1700000000"));
+
+        let TwitchApiError::RateLimited { reset } = TwitchApiError::rate_limited(&headers) else {
+            panic!("expected a rate limit error");
+        };
+        assert_eq!(reset.unwrap().timestamp(), 1_700_000_000);
+
+        assert!(matches!(
+            TwitchApiError::rate_limited(&HeaderMap::new()),
+            TwitchApiError::RateLimited { reset: None }
+        ));
+    }
+}
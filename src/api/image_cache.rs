@@ -0,0 +1,409 @@
+#![allow(dead_code)]
+
+use anyhow::{Context, Result};
+use ring::digest::{SHA256, digest};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tokio::sync::{Mutex, Semaphore};
+use tracing::{debug, warn};
+
+use crate::api::TwitchClient;
+use crate::api::models::{ImageDownload, Stream, User};
+use crate::config::AppDirs;
+
+const INDEX_FILE: &str = "index.json";
+const PROFILE_IMAGE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+const THUMBNAIL_TTL: Duration = Duration::from_secs(5 * 60);
+const MAX_CACHE_BYTES: u64 = 50 * 1024 * 1024;
+const MAX_CONCURRENT_DOWNLOADS: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    file: String,
+    etag: Option<String>,
+    size: u64,
+    fetched_at: u64,
+    last_used: u64,
+}
+
+pub struct ImageCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    downloads: Semaphore,
+}
+
+impl ImageCache {
+    pub async fn new(dir: PathBuf, max_bytes: u64, max_downloads: usize) -> Result<Self> {
+        fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create image cache directory: {dir:?}"))?;
+
+        let entries = match fs::read_to_string(dir.join(INDEX_FILE)).await {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                warn!("Discarding unreadable image cache index: {}", e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+
+        Ok(Self {
+            dir,
+            max_bytes,
+            entries: Mutex::new(entries),
+            downloads: Semaphore::new(max_downloads.max(1)),
+        })
+    }
+
+    pub async fn open_default(dirs: &AppDirs) -> Result<Self> {
+        let dir = dirs.cache.join("images");
+        Self::new(dir, MAX_CACHE_BYTES, MAX_CONCURRENT_DOWNLOADS).await
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub async fn profile_image(
+        &self,
+        client: &TwitchClient,
+        user: &User,
+        size: u32,
+    ) -> Result<PathBuf> {
+        let url = user.profile_image_with_size(size);
+        self.get(client, &url, PROFILE_IMAGE_TTL).await
+    }
+
+    pub async fn thumbnail(
+        &self,
+        client: &TwitchClient,
+        stream: &Stream,
+        width: u32,
+        height: u32,
+    ) -> Result<PathBuf> {
+        let url = stream.thumbnail_with_size(width, height);
+        self.get(client, &url, THUMBNAIL_TTL).await
+    }
+
+    pub async fn get(&self, client: &TwitchClient, url: &str, ttl: Duration) -> Result<PathBuf> {
+        let key = url.to_string();
+        let now = unix_now();
+
+        let cached = self.cached_entry(&key, now).await;
+        if let Some(entry) = &cached
+            && is_fresh(entry, now, ttl)
+        {
+            debug!("Image cache hit: {}", url);
+            return Ok(self.dir.join(&entry.file));
+        }
+
+        let _permit = self
+            .downloads
+            .acquire()
+            .await
+            .context("Image download limiter closed")?;
+
+        // Another request may have fetched the same image while this one waited
+        let cached = self.cached_entry(&key, now).await;
+        if let Some(entry) = &cached
+            && is_fresh(entry, unix_now(), ttl)
+        {
+            debug!("Image cache hit after waiting: {}", url);
+            return Ok(self.dir.join(&entry.file));
+        }
+
+        let etag = cached.as_ref().and_then(|entry| entry.etag.as_deref());
+        let download = match client.download_image(url, etag).await {
+            Ok(download) => download,
+            Err(e) => match &cached {
+                Some(entry) => {
+                    warn!("Using stale cached image for {}: {:#}", url, e);
+                    return Ok(self.dir.join(&entry.file));
+                }
+                None => return Err(e),
+            },
+        };
+
+        let mut entries = self.entries.lock().await;
+        let path = match download {
+            ImageDownload::NotModified => {
+                debug!("Image not modified, revalidated: {}", url);
+                let entry = entries
+                    .get_mut(&key)
+                    .context("Image cache entry vanished during revalidation")?;
+                entry.fetched_at = now;
+                entry.last_used = now;
+                self.dir.join(&entry.file)
+            }
+            ImageDownload::Modified { bytes, etag } => {
+                debug!("Downloaded image ({} bytes): {}", bytes.len(), url);
+                let file = file_name_for(url);
+                let path = self.dir.join(&file);
+                write_replacing(&path, &bytes)
+                    .await
+                    .with_context(|| format!("Failed to write cached image: {path:?}"))?;
+
+                entries.insert(
+                    key.clone(),
+                    CacheEntry {
+                        file,
+                        etag,
+                        size: bytes.len() as u64,
+                        fetched_at: now,
+                        last_used: now,
+                    },
+                );
+                self.evict(&mut entries, &key).await;
+                path
+            }
+        };
+
+        self.save_index(&entries).await;
+        Ok(path)
+    }
+
+    async fn cached_entry(&self, key: &str, now: u64) -> Option<CacheEntry> {
+        let mut entries = self.entries.lock().await;
+        match entries.get_mut(key) {
+            Some(entry) if self.dir.join(&entry.file).exists() => {
+                entry.last_used = now;
+                Some(entry.clone())
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn total_size(&self) -> u64 {
+        self.entries.lock().await.values().map(|e| e.size).sum()
+    }
+
+    // Drops least recently used images until the cache fits, keeping the newest one
+    async fn evict(&self, entries: &mut HashMap<String, CacheEntry>, keep: &str) {
+        let mut total: u64 = entries.values().map(|e| e.size).sum();
+        if total <= self.max_bytes {
+            return;
+        }
+
+        let mut by_age: Vec<(String, u64, u64)> = entries
+            .iter()
+            .filter(|(key, _)| key.as_str() != keep)
+            .map(|(key, entry)| (key.clone(), entry.last_used, entry.size))
+            .collect();
+        by_age.sort_by_key(|(_, last_used, _)| *last_used);
+
+        for (key, _, size) in by_age {
+            if total <= self.max_bytes {
+                break;
+            }
+
+            if let Some(entry) = entries.remove(&key) {
+                debug!("Evicting cached image: {}", key);
+                if let Err(e) = fs::remove_file(self.dir.join(&entry.file)).await {
+                    debug!("Failed to remove cached image {}: {}", entry.file, e);
+                }
+                total = total.saturating_sub(size);
+            }
+        }
+    }
+
+    async fn save_index(&self, entries: &HashMap<String, CacheEntry>) {
+        let result = match serde_json::to_string(entries) {
+            Ok(content) => write_replacing(&self.dir.join(INDEX_FILE), content.as_bytes()).await,
+            Err(e) => Err(e.into()),
+        };
+
+        if let Err(e) = result {
+            warn!("Failed to save image cache index: {}", e);
+        }
+    }
+}
+
+fn is_fresh(entry: &CacheEntry, now: u64, ttl: Duration) -> bool {
+    u128::from(now.saturating_sub(entry.fetched_at)) < ttl.as_millis()
+}
+
+// Callers hold the index lock, so the process id is enough to keep temp files apart
+async fn write_replacing(path: &Path, data: &[u8]) -> Result<()> {
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+
+    fs::write(&temp, data)
+        .await
+        .with_context(|| format!("Failed to write {temp:?}"))?;
+    fs::rename(&temp, path)
+        .await
+        .with_context(|| format!("Failed to replace {path:?}"))
+}
+
+// Named by a stable hash of the URL, so the index stays valid across builds
+fn file_name_for(url: &str) -> String {
+    let hash: String = digest(&SHA256, url.as_bytes()).as_ref()[..8]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+
+    let extension = url
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.split('?').next())
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, ext)| ext)
+        .filter(|ext| {
+            !ext.is_empty() && ext.len() <= 4 && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        .unwrap_or("img");
+
+    format!("{hash}.{extension}")
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_ACCOUNT;
+    use crate::testing::FakeTwitch;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::sync::RwLock;
+
+    async fn client_for(fake: &FakeTwitch) -> TwitchClient {
+        let config = fake.config();
+        let credentials = fake.credentials().await;
+        TwitchClient::new(
+            &config.twitch,
+            Arc::new(RwLock::new(config.clone())),
+            Arc::new(credentials),
+            DEFAULT_ACCOUNT,
+        )
+    }
+
+    fn image_requests(fake: &FakeTwitch) -> usize {
+        fake.state()
+            .requests
+            .iter()
+            .filter(|r| r.starts_with("/images/"))
+            .count()
+    }
+
+    #[tokio::test]
+    async fn fresh_images_are_served_from_disk_and_stale_ones_revalidated() {
+        let fake = FakeTwitch::start();
+        fake.state()
+            .images
+            .insert("alpha-70x70.png".to_string(), vec![1, 2, 3]);
+        let client = client_for(&fake).await;
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 1024, 2)
+            .await
+            .unwrap();
+        let url = fake.image_url("alpha-70x70.png");
+
+        let path = cache.get(&client, &url, PROFILE_IMAGE_TTL).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), [1, 2, 3]);
+        cache.get(&client, &url, PROFILE_IMAGE_TTL).await.unwrap();
+        assert_eq!(image_requests(&fake), 1);
+
+        let revalidated = cache.get(&client, &url, Duration::ZERO).await.unwrap();
+        assert_eq!(revalidated, path);
+        assert_eq!(image_requests(&fake), 2);
+
+        fake.state()
+            .images
+            .insert("alpha-70x70.png".to_string(), vec![4, 5, 6, 7]);
+        let path = cache.get(&client, &url, Duration::ZERO).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), [4, 5, 6, 7]);
+
+        let reopened = ImageCache::new(dir.path().to_path_buf(), 1024, 2)
+            .await
+            .unwrap();
+        reopened
+            .get(&client, &url, PROFILE_IMAGE_TTL)
+            .await
+            .unwrap();
+        assert_eq!(image_requests(&fake), 3);
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_images_are_evicted() {
+        let fake = FakeTwitch::start();
+        for name in ["a.png", "b.png", "c.png"] {
+            fake.state().images.insert(name.to_string(), vec![0; 40]);
+        }
+        let client = client_for(&fake).await;
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 100, 2)
+            .await
+            .unwrap();
+
+        let a = cache
+            .get(&client, &fake.image_url("a.png"), PROFILE_IMAGE_TTL)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let b = cache
+            .get(&client, &fake.image_url("b.png"), PROFILE_IMAGE_TTL)
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        cache
+            .get(&client, &fake.image_url("a.png"), PROFILE_IMAGE_TTL)
+            .await
+            .unwrap();
+        let c = cache
+            .get(&client, &fake.image_url("c.png"), PROFILE_IMAGE_TTL)
+            .await
+            .unwrap();
+
+        assert!(a.exists());
+        assert!(!b.exists());
+        assert!(c.exists());
+        assert_eq!(cache.total_size().await, 80);
+    }
+
+    #[tokio::test]
+    async fn waiting_requests_reuse_a_download_that_finished_meanwhile() {
+        let fake = FakeTwitch::start();
+        fake.state()
+            .images
+            .insert("alpha-70x70.png".to_string(), vec![1, 2, 3]);
+        let client = client_for(&fake).await;
+        let dir = TempDir::new().unwrap();
+        let cache = ImageCache::new(dir.path().to_path_buf(), 1024, 1)
+            .await
+            .unwrap();
+        let url = fake.image_url("alpha-70x70.png");
+
+        let (first, second) = tokio::join!(
+            cache.get(&client, &url, PROFILE_IMAGE_TTL),
+            cache.get(&client, &url, PROFILE_IMAGE_TTL)
+        );
+        assert_eq!(first.unwrap(), second.unwrap());
+        assert_eq!(image_requests(&fake), 1);
+
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    #[test]
+    fn size_variants_get_distinct_files() {
+        let small = file_name_for("https://example.invalid/alpha-profile_image-70x70.png");
+        let large = file_name_for("https://example.invalid/alpha-profile_image-300x300.png");
+
+        assert_ne!(small, large);
+        assert_eq!(small, "2519708ed665c416.png");
+        assert!(file_name_for("https://example.invalid/image").ends_with(".img"));
+    }
+}
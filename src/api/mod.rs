@@ -1,6 +1,11 @@
+pub mod backoff;
 pub mod client;
+pub mod error;
+pub mod image_cache;
 pub mod models;
 pub mod oauth;
+pub mod rate_limit;
 
 pub use client::TwitchClient;
+pub use error::TwitchApiError;
 pub use models::*;
@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 
+use chrono::{DateTime, TimeDelta, Utc};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -22,11 +23,14 @@ pub struct Stream {
     pub game_name: String,
     pub title: String,
     pub viewer_count: u32,
-    pub started_at: String,
+    pub started_at: DateTime<Utc>,
     pub language: String,
     pub thumbnail_url: String,
     pub tag_ids: Vec<String>,
     pub is_mature: bool,
+    // Names of the signed-in accounts following the channel, set when there are several
+    #[serde(skip)]
+    pub followed_by: Vec<String>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -68,10 +72,30 @@ pub struct TokenValidation {
 pub struct TokenResponse {
     pub access_token: String,
     pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub expires_in: Option<u64>,
     pub token_type: String,
     pub scope: Vec<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct DeviceCodeResponse {
+    pub device_code: String,
+    pub expires_in: u64,
+    pub interval: u64,
+    pub user_code: String,
+    pub verification_uri: String,
+}
+
+#[derive(Debug)]
+pub enum ImageDownload {
+    NotModified,
+    Modified {
+        bytes: Vec<u8>,
+        etag: Option<String>,
+    },
+}
+
 impl Stream {
     pub fn formatted_viewer_count(&self) -> String {
         format_viewer_count(self.viewer_count)
@@ -81,6 +105,23 @@ impl Stream {
         format!("https://www.twitch.tv/{}", self.user_login)
     }
 
+    pub fn live_duration(&self, now: DateTime<Utc>) -> Option<TimeDelta> {
+        let duration = now.signed_duration_since(self.started_at);
+        (duration >= TimeDelta::zero()).then_some(duration)
+    }
+
+    pub fn formatted_uptime(&self) -> String {
+        format_duration(self.live_duration(Utc::now()).unwrap_or_default())
+    }
+
+    pub fn followed_by_suffix(&self) -> String {
+        if self.followed_by.is_empty() {
+            String::new()
+        } else {
+            format!(" · {}", self.followed_by.join(", "))
+        }
+    }
+
     pub fn thumbnail_with_size(&self, width: u32, height: u32) -> String {
         self.thumbnail_url
             .replace("{width}", &width.to_string())
@@ -106,3 +147,51 @@ pub fn format_viewer_count(count: u32) -> String {
         count.to_string()
     }
 }
+
+pub fn format_duration(duration: TimeDelta) -> String {
+    let minutes = duration.num_minutes().max(0);
+    let (hours, minutes) = (minutes / 60, minutes % 60);
+
+    if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else {
+        format!("{minutes}m")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn started_at_is_parsed_and_formatted_as_uptime() {
+        let stream: Stream = serde_json::from_value(serde_json::json!({
+            "id": "s1",
+            "user_id": "u1",
+            "user_login": "alpha",
+            "user_name": "alpha",
+            "game_id": "509658",
+            "game_name": "Just Chatting",
+            "title": "hello",
+            "viewer_count": 10,
+            "started_at": "2024-01-01T12:00:00Z",
+            "language": "en",
+            "thumbnail_url": "",
+            "tag_ids": [],
+            "is_mature": false
+        }))
+        .unwrap();
+
+        let now: DateTime<Utc> = "2024-01-01T14:14:59Z".parse().unwrap();
+        assert_eq!(
+            format_duration(stream.live_duration(now).unwrap()),
+            "2h 14m"
+        );
+
+        let now: DateTime<Utc> = "2024-01-01T12:05:00Z".parse().unwrap();
+        assert_eq!(format_duration(stream.live_duration(now).unwrap()), "5m");
+
+        let before_start: DateTime<Utc> = "2024-01-01T11:00:00Z".parse().unwrap();
+        assert_eq!(stream.live_duration(before_start), None);
+    }
+}
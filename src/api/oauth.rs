@@ -6,22 +6,28 @@ use rustls::pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer};
 use serde::Deserialize;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::TcpListener;
 use tokio::sync::oneshot;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
 use tokio_rustls::{TlsAcceptor, rustls};
 use tracing::{debug, error, info, warn};
 
-use crate::api::models::TokenResponse;
+use crate::api::client::http_client;
+use crate::api::models::{DeviceCodeResponse, TokenResponse};
 
-const TWITCH_AUTH_URL: &str = "https://id.twitch.tv/oauth2/authorize";
-const TWITCH_TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
 const SCOPES: &[&str] = &["user:read:follows"];
 const REDIRECT_PORT: u16 = 17563;
 const REDIRECT_URI: &str = "https://localhost:17563";
+// Long enough to sign in, short enough that an abandoned browser tab doesn't hold the port
+const CALLBACK_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const DEVICE_CODE_GRANT: &str = "urn:ietf:params:oauth:grant-type:device_code";
 
 pub struct OAuthFlow {
     client_id: String,
+    oauth_base_url: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,8 +38,11 @@ struct AuthCallbackParams {
 }
 
 impl OAuthFlow {
-    pub fn new(client_id: String) -> Self {
-        Self { client_id }
+    pub fn new(client_id: String, oauth_base_url: String) -> Self {
+        Self {
+            client_id,
+            oauth_base_url,
+        }
     }
 
     fn generate_self_signed_cert() -> Result<rustls::ServerConfig> {
@@ -61,11 +70,129 @@ impl OAuthFlow {
 
         let (sender, receiver) = oneshot::channel();
 
-        self.start_callback_server_implicit(state, sender).await?;
+        let server = self.start_callback_server_implicit(state, sender).await?;
+
+        let result = match webbrowser::open(&auth_url).context("Failed to open browser") {
+            Ok(()) => match timeout(CALLBACK_TIMEOUT, receiver).await {
+                Ok(callback) => callback
+                    .context("Failed to receive OAuth callback")
+                    .and_then(|result| result),
+                Err(_) => Err(anyhow!(
+                    "Authorization was not completed within {} minutes",
+                    CALLBACK_TIMEOUT.as_secs() / 60
+                )),
+            },
+            Err(e) => Err(e),
+        };
+
+        // Frees the port for the next sign-in
+        server.abort();
+        result
+    }
+
+    pub async fn authenticate_device(&mut self) -> Result<TokenResponse> {
+        let client = http_client();
+        let scopes = SCOPES.join(" ");
+
+        let response = client
+            .post(format!("{}/device", self.oauth_base_url))
+            .form(&[("client_id", self.client_id.as_str()), ("scopes", &scopes)])
+            .send()
+            .await
+            .context("Failed to request device code")?;
+
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Device code request failed: {}", error_text));
+        }
+
+        let device: DeviceCodeResponse = response
+            .json()
+            .await
+            .context("Failed to parse device code response")?;
+
+        info!(
+            "Opening browser for authorization: {} (code: {})",
+            device.verification_uri, device.user_code
+        );
+        if let Err(e) = webbrowser::open(&device.verification_uri) {
+            warn!(
+                "Failed to open browser ({}), visit {} and enter code {}",
+                e, device.verification_uri, device.user_code
+            );
+        }
+
+        let deadline = tokio::time::Instant::now() + Duration::from_secs(device.expires_in);
+        let mut poll_interval = Duration::from_secs(device.interval.max(1));
+
+        loop {
+            tokio::time::sleep(poll_interval).await;
+
+            if tokio::time::Instant::now() >= deadline {
+                return Err(anyhow!("Device authorization expired"));
+            }
+
+            let response = client
+                .post(format!("{}/token", self.oauth_base_url))
+                .form(&[
+                    ("client_id", self.client_id.as_str()),
+                    ("scopes", &scopes),
+                    ("device_code", &device.device_code),
+                    ("grant_type", DEVICE_CODE_GRANT),
+                ])
+                .send()
+                .await
+                .context("Failed to poll device token")?;
+
+            if response.status().is_success() {
+                let token_response: TokenResponse = response
+                    .json()
+                    .await
+                    .context("Failed to parse token response")?;
+
+                info!("Device authorization completed");
+                return Ok(token_response);
+            }
+
+            let error_text = response.text().await.unwrap_or_default();
+            if error_text.contains("authorization_pending") {
+                debug!("Waiting for device authorization");
+            } else if error_text.contains("slow_down") {
+                poll_interval += Duration::from_secs(5);
+            } else {
+                return Err(anyhow!("Device authorization failed: {}", error_text));
+            }
+        }
+    }
+
+    pub async fn refresh(&self, refresh_token: &str) -> Result<TokenResponse> {
+        let client = http_client();
 
-        webbrowser::open(&auth_url).context("Failed to open browser")?;
+        let params = [
+            ("client_id", self.client_id.as_str()),
+            ("grant_type", "refresh_token"),
+            ("refresh_token", refresh_token),
+        ];
+
+        let response = client
+            .post(format!("{}/token", self.oauth_base_url))
+            .form(&params)
+            .send()
+            .await
+            .context("Failed to send token refresh request")?;
 
-        receiver.await.context("Failed to receive OAuth callback")?
+        if !response.status().is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("Token refresh failed: {}", error_text));
+        }
+
+        let token_response: TokenResponse = response
+            .json()
+            .await
+            .context("Failed to parse token refresh response")?;
+
+        debug!("Refreshed access token");
+        Ok(token_response)
     }
 
     async fn start_callback_server(
@@ -87,12 +214,15 @@ impl OAuthFlow {
         debug!("Server listening on 127.0.0.1:{}", REDIRECT_PORT);
 
         let client_id = self.client_id.clone();
+        let token_url = format!("{}/token", self.oauth_base_url);
 
         tokio::spawn(async move {
             while let Ok((stream, _)) = listener.accept().await {
                 match acceptor.accept(stream).await {
                     Ok(tls_stream) => {
-                        match Self::handle_https_request(tls_stream, &client_id, &state).await {
+                        match Self::handle_https_request(tls_stream, &client_id, &token_url, &state)
+                            .await
+                        {
                             Ok(Some(token_response)) => {
                                 let _ = sender.send(Ok(token_response));
                                 return;
@@ -118,6 +248,7 @@ impl OAuthFlow {
     async fn handle_https_request(
         mut stream: tokio_rustls::server::TlsStream<tokio::net::TcpStream>,
         client_id: &str,
+        token_url: &str,
         expected_state: &str,
     ) -> Result<Option<TokenResponse>> {
         let mut reader = BufReader::new(&mut stream);
@@ -174,7 +305,7 @@ impl OAuthFlow {
             }
 
             debug!("Exchanging authorization code for access token");
-            let token_response = Self::exchange_code_for_token(client_id, &code).await?;
+            let token_response = Self::exchange_code_for_token(client_id, token_url, &code).await?;
 
             return Ok(Some(token_response));
         }
@@ -201,8 +332,12 @@ impl OAuthFlow {
         }
     }
 
-    async fn exchange_code_for_token(client_id: &str, code: &str) -> Result<TokenResponse> {
-        let client = reqwest::Client::new();
+    async fn exchange_code_for_token(
+        client_id: &str,
+        token_url: &str,
+        code: &str,
+    ) -> Result<TokenResponse> {
+        let client = http_client();
 
         let params = [
             ("client_id", client_id),
@@ -212,7 +347,7 @@ impl OAuthFlow {
         ];
 
         let response = client
-            .post(TWITCH_TOKEN_URL)
+            .post(token_url)
             .form(&params)
             .send()
             .await
@@ -234,8 +369,8 @@ impl OAuthFlow {
 
     fn get_auth_url(&self, state: &str) -> String {
         format!(
-            "{}?client_id={}&redirect_uri={}&response_type=token&scope={}&state={}&force_verify=true",
-            TWITCH_AUTH_URL,
+            "{}/authorize?client_id={}&redirect_uri={}&response_type=token&scope={}&state={}&force_verify=true",
+            self.oauth_base_url,
             self.client_id,
             urlencoding::encode(REDIRECT_URI),
             SCOPES.join(" "),
@@ -247,7 +382,7 @@ impl OAuthFlow {
         &mut self,
         state: String,
         sender: oneshot::Sender<Result<TokenResponse>>,
-    ) -> Result<()> {
+    ) -> Result<JoinHandle<()>> {
         let tls_config = Self::generate_self_signed_cert()?;
         let acceptor = TlsAcceptor::from(Arc::new(tls_config));
 
@@ -261,7 +396,7 @@ impl OAuthFlow {
         );
         debug!("Server listening on 127.0.0.1:{}", REDIRECT_PORT);
 
-        tokio::spawn(async move {
+        let server = tokio::spawn(async move {
             while let Ok((stream, _)) = listener.accept().await {
                 match acceptor.accept(stream).await {
                     Ok(tls_stream) => {
@@ -285,7 +420,7 @@ impl OAuthFlow {
             }
         });
 
-        Ok(())
+        Ok(server)
     }
 
     async fn handle_https_request_implicit(
@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use reqwest::header::HeaderMap;
+use std::fmt;
+use std::time::Duration;
+
+// Keep a few points in reserve so token renewal and retries still go through
+const LOW_BUDGET: u32 = 5;
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub limit: u32,
+    pub remaining: u32,
+    pub reset: DateTime<Utc>,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+        Some(Self {
+            limit: header("Ratelimit-Limit")?.parse().ok()?,
+            remaining: header("Ratelimit-Remaining")?.parse().ok()?,
+            reset: reset_from_headers(headers)?,
+        })
+    }
+
+    pub fn is_low(&self) -> bool {
+        self.remaining <= LOW_BUDGET
+    }
+
+    // How long to hold the next request back, if at all
+    pub fn delay(&self, now: DateTime<Utc>) -> Option<Duration> {
+        if !self.is_low() {
+            return None;
+        }
+
+        Some(until(self.reset, now)).filter(|wait| !wait.is_zero())
+    }
+
+    // Counts a request before it is sent, so concurrent requests see the budget shrink
+    pub fn spend(&mut self) {
+        self.remaining = self.remaining.saturating_sub(1);
+    }
+
+    // Responses can arrive out of order, so within one window the lower count is kept
+    pub fn updated(self, reported: RateLimit) -> RateLimit {
+        if reported.reset == self.reset {
+            RateLimit {
+                remaining: self.remaining.min(reported.remaining),
+                ..reported
+            }
+        } else {
+            reported
+        }
+    }
+}
+
+impl fmt::Display for RateLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} points remaining, resets at {}",
+            self.remaining,
+            self.limit,
+            self.reset.to_rfc3339()
+        )
+    }
+}
+
+pub fn reset_from_headers(headers: &HeaderMap) -> Option<DateTime<Utc>> {
+    headers
+        .get("Ratelimit-Reset")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<i64>().ok())
+        .and_then(|secs| DateTime::from_timestamp(secs, 0))
+}
+
+pub fn until(reset: DateTime<Utc>, now: DateTime<Utc>) -> Duration {
+    (reset - now).to_std().unwrap_or_default().min(MAX_WAIT)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use reqwest::header::HeaderValue;
+
+    fn budget(remaining: u32, reset: DateTime<Utc>) -> RateLimit {
+        RateLimit {
+            limit: 800,
+            remaining,
+            reset,
+        }
+    }
+
+    #[test]
+    fn budget_is_parsed_from_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("Ratelimit-Limit", HeaderValue::from_static("800"));
+        headers.insert("Ratelimit-Remaining", HeaderValue::from_static("799"));
+        headers.insert("Ratelimit-Reset", HeaderValue::from_static("1700000000"));
+
+        let rate_limit = RateLimit::from_headers(&headers).unwrap();
+        assert_eq!(rate_limit.limit, 800);
+        assert_eq!(rate_limit.remaining, 799);
+        assert_eq!(rate_limit.reset.timestamp(), 1_700_000_000);
+
+        headers.remove("Ratelimit-Remaining");
+        assert!(RateLimit::from_headers(&headers).is_none());
+    }
+
+    #[test]
+    fn requests_are_delayed_only_when_budget_is_low() {
+        let now = Utc::now();
+        let reset = now + TimeDelta::seconds(3);
+
+        assert_eq!(budget(700, reset).delay(now), None);
+        assert_eq!(budget(2, reset).delay(now), Some(Duration::from_secs(3)));
+        assert_eq!(budget(0, now - TimeDelta::seconds(1)).delay(now), None);
+        assert_eq!(
+            budget(0, now + TimeDelta::hours(1)).delay(now),
+            Some(MAX_WAIT)
+        );
+    }
+
+    #[test]
+    fn spent_points_are_kept_until_the_window_resets() {
+        let now = Utc::now();
+        let mut local = budget(6, now);
+        local.spend();
+        local.spend();
+        assert_eq!(local.remaining, 4);
+        assert!(local.is_low());
+
+        assert_eq!(local.updated(budget(5, now)).remaining, 4);
+        assert_eq!(local.updated(budget(3, now)).remaining, 3);
+
+        let next_window = now + TimeDelta::seconds(60);
+        assert_eq!(local.updated(budget(799, next_window)).remaining, 799);
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tracing::{info, warn};
+
+use super::{APP_NAME, AppDirs};
+
+const DESKTOP_ENTRY: &str = include_str!("../../twitch-indicator.desktop");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutostartState {
+    Disabled,
+    Enabled,
+    Outdated,
+}
+
+pub struct Autostart {
+    entry_path: PathBuf,
+    executable: PathBuf,
+}
+
+impl Autostart {
+    pub fn new(autostart_dir: &Path, executable: PathBuf) -> Self {
+        Self {
+            entry_path: autostart_dir.join(format!("{APP_NAME}.desktop")),
+            executable,
+        }
+    }
+
+    pub fn for_current_exe(dirs: &AppDirs) -> Result<Self> {
+        let executable =
+            std::env::current_exe().context("Failed to get current executable path")?;
+
+        Ok(Self::new(&dirs.autostart, executable))
+    }
+
+    pub async fn state(&self) -> AutostartState {
+        match fs::read_to_string(&self.entry_path).await {
+            Ok(content) if content == self.desktop_entry() => AutostartState::Enabled,
+            Ok(_) => AutostartState::Outdated,
+            Err(_) => AutostartState::Disabled,
+        }
+    }
+
+    pub async fn set_enabled(&self, enabled: bool) -> Result<()> {
+        if enabled {
+            if let Some(parent) = self.entry_path.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("Failed to create autostart directory: {parent:?}"))?;
+            }
+
+            fs::write(&self.entry_path, self.desktop_entry())
+                .await
+                .with_context(|| {
+                    format!("Failed to write autostart entry: {:?}", self.entry_path)
+                })?;
+
+            info!("Enabled autostart: {:?}", self.entry_path);
+        } else if fs::try_exists(&self.entry_path).await.unwrap_or(false) {
+            fs::remove_file(&self.entry_path).await.with_context(|| {
+                format!("Failed to remove autostart entry: {:?}", self.entry_path)
+            })?;
+
+            info!("Disabled autostart: {:?}", self.entry_path);
+        }
+
+        Ok(())
+    }
+
+    pub async fn reconcile(&self, enabled: bool) -> Result<()> {
+        let state = self.state().await;
+        let expected = if enabled {
+            AutostartState::Enabled
+        } else {
+            AutostartState::Disabled
+        };
+
+        if state == expected {
+            return Ok(());
+        }
+
+        warn!(
+            "Autostart entry is {:?} but config has autostart = {}, updating {:?}",
+            state, enabled, self.entry_path
+        );
+        self.set_enabled(enabled).await
+    }
+
+    fn desktop_entry(&self) -> String {
+        let exec = exec_value(&self.executable.to_string_lossy());
+
+        let mut entry: String = DESKTOP_ENTRY
+            .lines()
+            .map(|line| {
+                if line.starts_with("Exec=") {
+                    format!("Exec={exec}\n")
+                } else {
+                    format!("{line}\n")
+                }
+            })
+            .collect();
+        entry.push_str("X-GNOME-Autostart-enabled=true\n");
+        entry
+    }
+}
+
+// Quotes and escapes a single-argument Exec value as the Desktop Entry spec asks
+fn exec_value(executable: &str) -> String {
+    const RESERVED: &[char] = &[
+        '"', '\'', '\\', '>', '<', '~', '|', '&', ';', '$', '*', '?', '#', '(', ')', '`',
+    ];
+
+    // % introduces field codes, even inside quotes
+    let arg = executable.replace('%', "%%");
+    let arg = if arg.contains(|c: char| c.is_whitespace() || RESERVED.contains(&c)) {
+        let mut quoted = String::from('"');
+        for c in arg.chars() {
+            if matches!(c, '"' | '`' | '$' | '\\') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    } else {
+        arg
+    };
+
+    // The value is read as a string first, which undoes one level of backslashes
+    arg.chars()
+        .map(|c| match c {
+            '\\' => "\\\\".to_string(),
+            '\n' => "\\n".to_string(),
+            '\t' => "\\t".to_string(),
+            '\r' => "\\r".to_string(),
+            c => c.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn toggles_entry_with_current_executable() {
+        let dir = tempfile::tempdir().unwrap();
+        let autostart = Autostart::new(dir.path(), PathBuf::from("/opt/twitch indicator/bin"));
+
+        assert_eq!(autostart.state().await, AutostartState::Disabled);
+
+        autostart.set_enabled(true).await.unwrap();
+        assert_eq!(autostart.state().await, AutostartState::Enabled);
+        let content = std::fs::read_to_string(dir.path().join("twitch-indicator.desktop")).unwrap();
+        assert!(content.contains("Exec=\"/opt/twitch indicator/bin\"\n"));
+        assert!(content.contains("Name=Twitch Indicator\n"));
+
+        autostart.set_enabled(false).await.unwrap();
+        assert_eq!(autostart.state().await, AutostartState::Disabled);
+    }
+
+    #[test]
+    fn exec_escapes_reserved_characters() {
+        assert_eq!(
+            exec_value("/usr/bin/twitch-indicator"),
+            "/usr/bin/twitch-indicator"
+        );
+        assert_eq!(exec_value("/opt/100%/bin"), "/opt/100%%/bin");
+        assert_eq!(
+            exec_value("/opt/50% \"off\"/bin"),
+            r#""/opt/50%% \\"off\\"/bin""#
+        );
+        assert_eq!(exec_value("/tmp/a$b\\c`d"), r#""/tmp/a\\$b\\\\c\\`d""#);
+    }
+
+    #[tokio::test]
+    async fn reconcile_fixes_drift() {
+        let dir = tempfile::tempdir().unwrap();
+        let old = Autostart::new(dir.path(), PathBuf::from("/usr/bin/old-indicator"));
+        let current = Autostart::new(dir.path(), PathBuf::from("/usr/bin/twitch-indicator"));
+
+        old.set_enabled(true).await.unwrap();
+        assert_eq!(current.state().await, AutostartState::Outdated);
+
+        current.reconcile(true).await.unwrap();
+        assert_eq!(current.state().await, AutostartState::Enabled);
+
+        current.reconcile(false).await.unwrap();
+        assert_eq!(current.state().await, AutostartState::Disabled);
+    }
+}
@@ -0,0 +1,653 @@
+use anyhow::{Context, Result, anyhow, bail};
+use ring::aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+use secret_service::{Collection, EncryptionType, Item, SecretService};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use tokio::time::timeout;
+use tracing::{debug, info, warn};
+
+use super::{APP_NAME, AppDirs};
+
+pub const DEFAULT_ACCOUNT: &str = "default";
+
+const SECRETS_FILE: &str = "credentials.enc";
+const KEY_FILE: &str = "credentials.key";
+const LOCK_FILE: &str = "credentials.lock";
+const KEY_LEN: usize = 32;
+const PROMPT_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Tokens {
+    pub access_token: Option<String>,
+    pub refresh_token: Option<String>,
+}
+
+impl Tokens {
+    pub fn is_empty(&self) -> bool {
+        self.access_token.is_none() && self.refresh_token.is_none()
+    }
+}
+
+pub enum CredentialStore {
+    SecretService(SecretServiceStore),
+    EncryptedFile(EncryptedFileStore),
+}
+
+impl CredentialStore {
+    // Prefers the desktop keyring and falls back to an encrypted file when none is running
+    pub async fn open_default(dirs: &AppDirs) -> Result<Self> {
+        match SecretServiceStore::open_default().await {
+            Ok(store) => return Ok(Self::SecretService(store)),
+            Err(e) => warn!(
+                "Secret Service unavailable, storing credentials in an encrypted file: {:#}",
+                e
+            ),
+        }
+
+        Ok(Self::EncryptedFile(EncryptedFileStore::open_default(dirs)))
+    }
+
+    pub fn encrypted_file(secrets_file: PathBuf, key_file: PathBuf) -> Self {
+        Self::EncryptedFile(EncryptedFileStore::new(secrets_file, key_file))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::SecretService(_) => "Secret Service",
+            Self::EncryptedFile(_) => "encrypted file",
+        }
+    }
+
+    pub async fn load(&self, account: &str) -> Result<Tokens> {
+        match self {
+            Self::SecretService(store) => store.load(account).await,
+            Self::EncryptedFile(store) => store.load(account).await,
+        }
+    }
+
+    pub async fn save(&self, account: &str, tokens: &Tokens) -> Result<()> {
+        match self {
+            Self::SecretService(store) => store.save(account, tokens).await,
+            Self::EncryptedFile(store) => store.save(account, tokens).await,
+        }
+    }
+
+    pub async fn clear(&self, account: &str) -> Result<()> {
+        match self {
+            Self::SecretService(store) => store.clear(account).await,
+            Self::EncryptedFile(store) => store.clear(account).await,
+        }
+    }
+
+    // Older versions kept tokens in config.toml; move them here and drop them from the file
+    pub async fn migrate_legacy_tokens(&self, config_file: &Path) -> Result<bool> {
+        let content = match fs::read_to_string(config_file).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(false),
+            Err(e) => {
+                return Err(e)
+                    .with_context(|| format!("Failed to read config file: {config_file:?}"));
+            }
+        };
+
+        let mut document: toml::Table = toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {config_file:?}"))?;
+        let Some(twitch) = document.get_mut("twitch").and_then(|t| t.as_table_mut()) else {
+            return Ok(false);
+        };
+
+        let legacy = Tokens {
+            access_token: take_string(twitch, "access_token"),
+            refresh_token: take_string(twitch, "refresh_token"),
+        };
+        if legacy.is_empty() {
+            return Ok(false);
+        }
+
+        if self.load(DEFAULT_ACCOUNT).await?.access_token.is_none() {
+            self.save(DEFAULT_ACCOUNT, &legacy).await?;
+        }
+
+        let content =
+            toml::to_string_pretty(&document).context("Failed to serialize configuration")?;
+        write_private(config_file, content.as_bytes())
+            .await
+            .with_context(|| format!("Failed to write config file: {config_file:?}"))?;
+
+        info!(
+            "Moved tokens from {:?} into the {} credential store",
+            config_file,
+            self.name()
+        );
+        Ok(true)
+    }
+}
+
+fn take_string(table: &mut toml::Table, key: &str) -> Option<String> {
+    table
+        .remove(key)
+        .and_then(|value| value.as_str().map(str::to_string))
+}
+
+pub struct SecretServiceStore {
+    service: SecretService<'static>,
+    prompt_timeout: Duration,
+}
+
+impl SecretServiceStore {
+    pub async fn open_default() -> Result<Self> {
+        Self::open(EncryptionType::Dh).await
+    }
+
+    // A service without a default keyring can't store anything, so it counts as unavailable
+    pub async fn open(encryption: EncryptionType) -> Result<Self> {
+        let service = SecretService::connect(encryption)
+            .await
+            .context("Failed to open Secret Service session")?;
+        default_collection(&service).await?;
+
+        debug!("Opened Secret Service session");
+        Ok(Self {
+            service,
+            prompt_timeout: PROMPT_TIMEOUT,
+        })
+    }
+
+    pub fn with_prompt_timeout(mut self, prompt_timeout: Duration) -> Self {
+        self.prompt_timeout = prompt_timeout;
+        self
+    }
+
+    pub async fn load(&self, account: &str) -> Result<Tokens> {
+        let Some(item) = self.find(account).await? else {
+            return Ok(Tokens::default());
+        };
+
+        let value = item
+            .get_secret()
+            .await
+            .context("Failed to read credentials from Secret Service")?;
+        serde_json::from_slice(&value).context("Failed to parse stored credentials")
+    }
+
+    pub async fn save(&self, account: &str, tokens: &Tokens) -> Result<()> {
+        let collection = default_collection(&self.service).await?;
+        let plaintext = serde_json::to_vec(tokens).context("Failed to serialize credentials")?;
+
+        self.prompted(collection.create_item(
+            &format!("Twitch Indicator credentials ({account})"),
+            attributes(account),
+            &plaintext,
+            true,
+            "application/json",
+        ))
+        .await
+        .context("Failed to store credentials in Secret Service")?;
+        Ok(())
+    }
+
+    pub async fn clear(&self, account: &str) -> Result<()> {
+        let Some(item) = self.find(account).await? else {
+            return Ok(());
+        };
+
+        self.prompted(item.delete())
+            .await
+            .context("Failed to delete credentials from Secret Service")
+    }
+
+    async fn find(&self, account: &str) -> Result<Option<Item<'_>>> {
+        let items = self
+            .service
+            .search_items(attributes(account))
+            .await
+            .context("Failed to search Secret Service")?;
+
+        if let Some(item) = items.unlocked.into_iter().next() {
+            return Ok(Some(item));
+        }
+        let Some(item) = items.locked.into_iter().next() else {
+            return Ok(None);
+        };
+
+        self.prompted(item.unlock())
+            .await
+            .context("Failed to unlock stored credentials")?;
+        Ok(Some(item))
+    }
+
+    // A locked keyring shows a prompt first, which may be dismissed or left unanswered
+    async fn prompted<T>(
+        &self,
+        operation: impl Future<Output = Result<T, secret_service::Error>>,
+    ) -> Result<T> {
+        match timeout(self.prompt_timeout, operation).await {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(secret_service::Error::Prompt)) => bail!("Keyring prompt was dismissed"),
+            Ok(Err(e)) => Err(e.into()),
+            Err(_) => bail!(
+                "Keyring prompt was not answered within {}s",
+                self.prompt_timeout.as_secs()
+            ),
+        }
+    }
+}
+
+async fn default_collection<'a>(service: &'a SecretService<'_>) -> Result<Collection<'a>> {
+    match service.get_default_collection().await {
+        Ok(collection) => Ok(collection),
+        Err(secret_service::Error::NoResult) => bail!("Secret Service has no default keyring"),
+        Err(e) => Err(e).context("Failed to look up the default keyring"),
+    }
+}
+
+fn attributes(account: &str) -> HashMap<&str, &str> {
+    HashMap::from([("application", APP_NAME), ("account", account)])
+}
+
+pub struct EncryptedFileStore {
+    secrets_file: PathBuf,
+    key_file: PathBuf,
+}
+
+impl EncryptedFileStore {
+    pub fn new(secrets_file: PathBuf, key_file: PathBuf) -> Self {
+        Self {
+            secrets_file,
+            key_file,
+        }
+    }
+
+    // The key lives apart from the config directory so copying that directory doesn't leak tokens
+    pub fn open_default(dirs: &AppDirs) -> Self {
+        Self::new(dirs.config.join(SECRETS_FILE), dirs.data.join(KEY_FILE))
+    }
+
+    pub async fn load(&self, account: &str) -> Result<Tokens> {
+        Ok(self
+            .read_accounts()
+            .await?
+            .remove(account)
+            .unwrap_or_default())
+    }
+
+    pub async fn save(&self, account: &str, tokens: &Tokens) -> Result<()> {
+        let _lock = self.lock().await?;
+        let mut accounts = self.read_accounts().await?;
+        accounts.insert(account.to_string(), tokens.clone());
+        self.write_accounts(&accounts).await
+    }
+
+    pub async fn clear(&self, account: &str) -> Result<()> {
+        let _lock = self.lock().await?;
+        let mut accounts = self.read_accounts().await?;
+        if accounts.remove(account).is_some() {
+            self.write_accounts(&accounts).await?;
+        }
+        Ok(())
+    }
+
+    // Another process, such as --add-account next to the running indicator, may be
+    // updating the file at the same time; the lock is released when the file is dropped
+    async fn lock(&self) -> Result<std::fs::File> {
+        let path = self.secrets_file.with_file_name(LOCK_FILE);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create directory: {parent:?}"))?;
+        }
+
+        tokio::task::spawn_blocking(move || {
+            use std::os::unix::fs::OpenOptionsExt;
+
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .mode(0o600)
+                .open(&path)
+                .with_context(|| format!("Failed to open {path:?}"))?;
+            file.lock()
+                .with_context(|| format!("Failed to lock {path:?}"))?;
+            Ok(file)
+        })
+        .await?
+    }
+
+    async fn read_accounts(&self) -> Result<BTreeMap<String, Tokens>> {
+        let data = match fs::read(&self.secrets_file).await {
+            Ok(data) => data,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(BTreeMap::new()),
+            Err(e) => {
+                return Err(e).with_context(|| {
+                    format!("Failed to read credentials: {:?}", self.secrets_file)
+                });
+            }
+        };
+
+        // Tokens of every account are in this file, so never overwrite it when it can't be read
+        let key = self.read_key().await?.ok_or_else(|| {
+            anyhow!(
+                "Credential key {:?} is missing; remove {:?} to sign in again",
+                self.key_file,
+                self.secrets_file
+            )
+        })?;
+        let plaintext = open(&key, data).with_context(|| {
+            format!(
+                "Failed to decrypt {:?}; remove it to sign in again",
+                self.secrets_file
+            )
+        })?;
+        serde_json::from_slice(&plaintext).context("Failed to parse stored credentials")
+    }
+
+    async fn write_accounts(&self, accounts: &BTreeMap<String, Tokens>) -> Result<()> {
+        let key = match self.read_key().await? {
+            Some(key) => key,
+            None => self.create_key().await?,
+        };
+        let plaintext = serde_json::to_vec(accounts).context("Failed to serialize credentials")?;
+        write_private(&self.secrets_file, &seal(&key, plaintext)?).await
+    }
+
+    async fn read_key(&self) -> Result<Option<Vec<u8>>> {
+        match fs::read(&self.key_file).await {
+            Ok(key) if key.len() == KEY_LEN => Ok(Some(key)),
+            Ok(_) => bail!("Credential key {:?} is malformed", self.key_file),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e)
+                .with_context(|| format!("Failed to read credential key: {:?}", self.key_file)),
+        }
+    }
+
+    async fn create_key(&self) -> Result<Vec<u8>> {
+        let mut key = vec![0; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| anyhow!("Failed to generate credential key"))?;
+        write_private(&self.key_file, &key).await?;
+
+        debug!("Created credential key: {:?}", self.key_file);
+        Ok(key)
+    }
+}
+
+fn cipher(key: &[u8]) -> Result<LessSafeKey> {
+    let key =
+        UnboundKey::new(&CHACHA20_POLY1305, key).map_err(|_| anyhow!("Invalid credential key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn seal(key: &[u8], mut data: Vec<u8>) -> Result<Vec<u8>> {
+    let mut nonce = [0; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| anyhow!("Failed to generate nonce"))?;
+
+    cipher(key)?
+        .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::empty(), &mut data)
+        .map_err(|_| anyhow!("Failed to encrypt credentials"))?;
+
+    let mut sealed = nonce.to_vec();
+    sealed.append(&mut data);
+    Ok(sealed)
+}
+
+fn open(key: &[u8], mut data: Vec<u8>) -> Result<Vec<u8>> {
+    if data.len() < NONCE_LEN {
+        bail!("Credential file is truncated");
+    }
+
+    let mut ciphertext = data.split_off(NONCE_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(&data).map_err(|_| anyhow!("Invalid nonce"))?;
+    let plaintext = cipher(key)?
+        .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt credentials"))?;
+
+    Ok(plaintext.to_vec())
+}
+
+async fn write_private(path: &Path, data: &[u8]) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .with_context(|| format!("Failed to create directory: {parent:?}"))?;
+    }
+
+    // Written beside the target and renamed over it, so a crash leaves either version intact
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    let temp = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&temp)
+        .await
+        .with_context(|| format!("Failed to open {temp:?}"))?;
+    file.write_all(data)
+        .await
+        .with_context(|| format!("Failed to write {temp:?}"))?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&temp, path)
+        .await
+        .with_context(|| format!("Failed to replace {path:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Config;
+    use crate::testing::fake_secret_service::PromptReply;
+    use crate::testing::{FakeSecretService, PrivateBus};
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    fn tokens(access: &str, refresh: &str) -> Tokens {
+        Tokens {
+            access_token: Some(access.to_string()),
+            refresh_token: Some(refresh.to_string()),
+        }
+    }
+
+    fn file_store(dir: &TempDir) -> CredentialStore {
+        CredentialStore::encrypted_file(
+            dir.path().join("config").join(SECRETS_FILE),
+            dir.path().join("data").join(KEY_FILE),
+        )
+    }
+
+    #[tokio::test]
+    async fn encrypted_file_round_trips_without_plain_text() {
+        let dir = TempDir::new().unwrap();
+        let store = file_store(&dir);
+
+        assert!(store.load(DEFAULT_ACCOUNT).await.unwrap().is_empty());
+        store
+            .save(DEFAULT_ACCOUNT, &tokens("secret-access", "secret-refresh"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.load(DEFAULT_ACCOUNT).await.unwrap(),
+            tokens("secret-access", "secret-refresh")
+        );
+
+        let raw = std::fs::read(dir.path().join("config").join(SECRETS_FILE)).unwrap();
+        assert!(!String::from_utf8_lossy(&raw).contains("secret-access"));
+        let key = std::fs::metadata(dir.path().join("data").join(KEY_FILE)).unwrap();
+        assert_eq!(key.permissions().mode() & 0o777, 0o600);
+
+        store.clear(DEFAULT_ACCOUNT).await.unwrap();
+        assert!(store.load(DEFAULT_ACCOUNT).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn unreadable_credentials_are_never_overwritten() {
+        let dir = TempDir::new().unwrap();
+        let store = file_store(&dir);
+        let secrets_file = dir.path().join("config").join(SECRETS_FILE);
+        let key_file = dir.path().join("data").join(KEY_FILE);
+        store
+            .save(DEFAULT_ACCOUNT, &tokens("access", "refresh"))
+            .await
+            .unwrap();
+        let sealed = std::fs::read(&secrets_file).unwrap();
+
+        std::fs::remove_file(&key_file).unwrap();
+        assert!(store.load(DEFAULT_ACCOUNT).await.is_err());
+        assert!(store.save("other", &tokens("a", "r")).await.is_err());
+        assert!(!key_file.exists());
+
+        std::fs::write(&key_file, b"short").unwrap();
+        assert!(store.save("other", &tokens("a", "r")).await.is_err());
+        assert_eq!(std::fs::read(&key_file).unwrap(), b"short");
+        assert_eq!(std::fs::read(&secrets_file).unwrap(), sealed);
+    }
+
+    #[tokio::test]
+    async fn concurrent_saves_keep_every_account() {
+        let dir = TempDir::new().unwrap();
+        let stores: Vec<CredentialStore> = (0..8).map(|_| file_store(&dir)).collect();
+
+        futures_util::future::try_join_all(stores.iter().enumerate().map(
+            |(i, store)| async move {
+                store
+                    .save(&format!("account-{i}"), &tokens("access", "refresh"))
+                    .await
+            },
+        ))
+        .await
+        .unwrap();
+
+        for i in 0..8 {
+            assert!(
+                !stores[0]
+                    .load(&format!("account-{i}"))
+                    .await
+                    .unwrap()
+                    .is_empty()
+            );
+        }
+        let leftovers: Vec<_> = std::fs::read_dir(dir.path().join("config"))
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty());
+    }
+
+    async fn open_secret_service(bus: &PrivateBus) -> Result<SecretServiceStore> {
+        let _session_bus = bus.set_as_session_bus().await;
+        SecretServiceStore::open(EncryptionType::Plain).await
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon, run with --ignored"]
+    async fn secret_service_stores_one_item_per_account() {
+        let bus = PrivateBus::start();
+        let keyring = FakeSecretService::start(&bus).await;
+        let store = CredentialStore::SecretService(open_secret_service(&bus).await.unwrap());
+
+        store
+            .save(DEFAULT_ACCOUNT, &tokens("first", "refresh"))
+            .await
+            .unwrap();
+        store
+            .save(DEFAULT_ACCOUNT, &tokens("second", "refresh"))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.load(DEFAULT_ACCOUNT).await.unwrap(),
+            tokens("second", "refresh")
+        );
+
+        let items = keyring.items();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].attributes["application"], APP_NAME);
+        assert_eq!(items[0].attributes["account"], DEFAULT_ACCOUNT);
+        assert_eq!(
+            serde_json::from_slice::<Tokens>(&items[0].value).unwrap(),
+            tokens("second", "refresh")
+        );
+
+        assert!(store.load("other").await.unwrap().is_empty());
+        store.clear(DEFAULT_ACCOUNT).await.unwrap();
+        assert!(store.load(DEFAULT_ACCOUNT).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon, run with --ignored"]
+    async fn locked_secret_is_unlocked_through_prompt() {
+        let bus = PrivateBus::start();
+        let keyring = FakeSecretService::start(&bus).await;
+        let store = open_secret_service(&bus)
+            .await
+            .unwrap()
+            .with_prompt_timeout(Duration::from_millis(200));
+        store
+            .save(DEFAULT_ACCOUNT, &tokens("access", "refresh"))
+            .await
+            .unwrap();
+
+        keyring.lock_all();
+        keyring.set_prompt_reply(PromptReply::Dismiss);
+        let err = store.load(DEFAULT_ACCOUNT).await.unwrap_err();
+        assert!(format!("{err:#}").contains("dismissed"), "{err:#}");
+
+        keyring.set_prompt_reply(PromptReply::Ignore);
+        let err = store.load(DEFAULT_ACCOUNT).await.unwrap_err();
+        assert!(format!("{err:#}").contains("not answered"), "{err:#}");
+
+        keyring.set_prompt_reply(PromptReply::Unlock);
+        assert_eq!(
+            store.load(DEFAULT_ACCOUNT).await.unwrap(),
+            tokens("access", "refresh")
+        );
+        assert!(!keyring.items()[0].locked);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon, run with --ignored"]
+    async fn secret_service_without_default_keyring_is_unavailable() {
+        let bus = PrivateBus::start();
+        let keyring = FakeSecretService::start(&bus).await;
+        keyring.remove_default_collection();
+
+        let err = open_secret_service(&bus).await.err().unwrap();
+        assert!(format!("{err:#}").contains("no default keyring"), "{err:#}");
+    }
+
+    #[tokio::test]
+    async fn plain_text_tokens_are_migrated_once() {
+        let dir = TempDir::new().unwrap();
+        let store = file_store(&dir);
+        let config_file = dir.path().join("config.toml");
+
+        let mut legacy = toml::Table::try_from(Config::default()).unwrap();
+        let twitch = legacy["twitch"].as_table_mut().unwrap();
+        twitch.insert("access_token".to_string(), "old-access".into());
+        twitch.insert("refresh_token".to_string(), "old-refresh".into());
+        std::fs::write(&config_file, toml::to_string(&legacy).unwrap()).unwrap();
+
+        assert!(store.migrate_legacy_tokens(&config_file).await.unwrap());
+        assert_eq!(
+            store.load(DEFAULT_ACCOUNT).await.unwrap(),
+            tokens("old-access", "old-refresh")
+        );
+
+        let content = std::fs::read_to_string(&config_file).unwrap();
+        assert!(!content.contains("old-access"));
+        assert!(Config::load_from(&config_file).await.is_ok());
+        assert!(!store.migrate_legacy_tokens(&config_file).await.unwrap());
+    }
+}
@@ -2,14 +2,27 @@
 
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 use tokio::fs;
 use tracing::{debug, error, info};
 
+mod autostart;
+pub(crate) mod credentials;
+mod paths;
+mod watcher;
+
+pub use autostart::Autostart;
+pub use credentials::{CredentialStore, DEFAULT_ACCOUNT, Tokens};
+pub use paths::AppDirs;
+pub use watcher::ConfigWatcher;
+
 const APP_NAME: &str = "twitch-indicator";
 const CONFIG_FILE: &str = "config.toml";
+const DEFAULT_API_BASE_URL: &str = "https://api.twitch.tv/helix";
+const DEFAULT_OAUTH_BASE_URL: &str = "https://id.twitch.tv/oauth2";
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Config {
     pub twitch: TwitchConfig,
     pub notifications: NotificationConfig,
@@ -18,36 +31,178 @@ pub struct Config {
     pub stream_open: StreamOpenConfig,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TwitchConfig {
     pub client_id: String,
     pub redirect_uri: String,
-    pub access_token: Option<String>,
-    pub refresh_token: Option<String>,
     pub refresh_interval_minutes: u64,
+    #[serde(default = "default_api_base_url")]
+    pub api_base_url: String,
+    #[serde(default = "default_oauth_base_url")]
+    pub oauth_base_url: String,
+    #[serde(default)]
+    pub auth_mode: AuthMode,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub accounts: Vec<AccountConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountConfig {
+    pub id: String,
+    #[serde(default)]
+    pub login: String,
+    #[serde(default = "default_account_notifications")]
+    pub notifications: bool,
+}
+
+impl AccountConfig {
+    pub fn new(id: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            login: String::new(),
+            notifications: default_account_notifications(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthMode {
+    #[default]
+    Implicit,
+    DeviceCode,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_api_base_url() -> String {
+    DEFAULT_API_BASE_URL.to_string()
+}
+
+fn default_oauth_base_url() -> String {
+    DEFAULT_OAUTH_BASE_URL.to_string()
+}
+
+fn default_show_uptime() -> bool {
+    true
+}
+
+fn default_account_notifications() -> bool {
+    true
+}
+
+fn default_recently_ended_hours() -> u64 {
+    3
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct NotificationConfig {
     pub enabled: bool,
     pub show_game: bool,
     pub show_viewer_count: bool,
+    #[serde(default = "default_show_uptime")]
+    pub show_uptime: bool,
     pub timeout_ms: u32,
+    #[serde(default)]
+    pub channel_policies: BTreeMap<String, NotificationPolicy>,
+    #[serde(default)]
+    pub rules: Vec<NotificationRule>,
+    #[serde(default)]
+    pub notify_category_change: bool,
+    #[serde(default)]
+    pub notify_title_change: bool,
+    #[serde(default)]
+    pub notify_offline: bool,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRule {
+    #[serde(default)]
+    pub action: RuleAction,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_regex: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_mature: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_viewers: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RuleAction {
+    #[default]
+    Include,
+    Exclude,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationPolicy {
+    Always,
+    Never,
+    #[default]
+    Default,
+}
+
+impl NotificationConfig {
+    pub fn channel_policy(&self, user_id: &str) -> NotificationPolicy {
+        self.channel_policies
+            .get(user_id)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct UiConfig {
     pub show_selected_channels_on_top: bool,
     pub dark_theme: bool,
+    #[serde(default)]
+    pub favourite_channels: Vec<FavouriteChannel>,
+    #[serde(default = "default_recently_ended_hours")]
+    pub recently_ended_hours: u64,
+    #[serde(default)]
+    pub tray_badge: TrayBadge,
+    #[serde(default)]
+    pub menu_layout: MenuLayout,
+    #[serde(default = "default_show_uptime")]
+    pub show_uptime: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MenuLayout {
+    #[default]
+    Flat,
+    ByGame,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrayBadge {
+    #[default]
+    Live,
+    Favourites,
+    Off,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FavouriteChannel {
+    pub broadcaster_id: String,
+    pub broadcaster_name: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GeneralConfig {
     pub autostart: bool,
     pub minimize_to_tray: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct StreamOpenConfig {
     pub program: Option<String>,
     pub arguments: Vec<String>,
@@ -61,19 +216,32 @@ impl Default for Config {
             twitch: TwitchConfig {
                 client_id: "pdnu3rmmjndvi58vd5f19l5rxqvu6c".to_string(),
                 redirect_uri: "https://localhost:17563".to_string(),
-                access_token: None,
-                refresh_token: None,
                 refresh_interval_minutes: 2,
+                api_base_url: default_api_base_url(),
+                oauth_base_url: default_oauth_base_url(),
+                auth_mode: AuthMode::Implicit,
+                accounts: vec![],
             },
             notifications: NotificationConfig {
                 enabled: true,
                 show_game: true,
                 show_viewer_count: true,
+                show_uptime: default_show_uptime(),
                 timeout_ms: 5000,
+                channel_policies: BTreeMap::new(),
+                rules: vec![],
+                notify_category_change: false,
+                notify_title_change: false,
+                notify_offline: false,
             },
             ui: UiConfig {
                 show_selected_channels_on_top: true,
                 dark_theme: true,
+                favourite_channels: vec![],
+                recently_ended_hours: default_recently_ended_hours(),
+                tray_badge: TrayBadge::Live,
+                menu_layout: MenuLayout::Flat,
+                show_uptime: default_show_uptime(),
             },
             general: GeneralConfig {
                 autostart: false,
@@ -90,21 +258,14 @@ impl Default for Config {
 }
 
 impl Config {
-    pub async fn load_or_create(config_path: Option<String>) -> Result<Self> {
-        let config_dir = Self::get_config_dir()?;
+    pub async fn load_or_create(config_path: Option<String>, dirs: &AppDirs) -> Result<Self> {
         let config_file = match config_path {
             Some(path) => PathBuf::from(path),
-            None => config_dir.join(CONFIG_FILE),
+            None => dirs.config_file(),
         };
 
         if config_file.exists() {
-            debug!("Loading config from: {:?}", config_file);
-            let content = fs::read_to_string(&config_file)
-                .await
-                .with_context(|| format!("Failed to read config file: {config_file:?}"))?;
-
-            let config: Config = toml::from_str(&content)
-                .with_context(|| format!("Failed to parse config file: {config_file:?}"))?;
+            let config = Self::load_from(&config_file).await?;
 
             info!("Configuration loaded successfully");
             Ok(config)
@@ -116,6 +277,20 @@ impl Config {
         }
     }
 
+    pub async fn load_from(config_file: &Path) -> Result<Self> {
+        debug!("Loading config from: {:?}", config_file);
+        let content = fs::read_to_string(config_file)
+            .await
+            .with_context(|| format!("Failed to read config file: {config_file:?}"))?;
+
+        toml::from_str(&content)
+            .with_context(|| format!("Failed to parse config file: {config_file:?}"))
+    }
+
+    pub fn refresh_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.twitch.refresh_interval_minutes * 60)
+    }
+
     pub async fn save(&self, config_file: &PathBuf) -> Result<()> {
         if let Some(parent) = config_file.parent() {
             fs::create_dir_all(parent)
@@ -133,38 +308,8 @@ impl Config {
         Ok(())
     }
 
-    pub async fn save_default(&self) -> Result<()> {
-        let config_dir = Self::get_config_dir()?;
-        let config_file = config_dir.join(CONFIG_FILE);
-        self.save(&config_file).await
-    }
-
-    pub fn get_config_dir() -> Result<PathBuf> {
-        dirs::config_dir()
-            .map(|dir| dir.join(APP_NAME))
-            .context("Failed to get config directory")
-    }
-
-    pub fn get_cache_dir() -> Result<PathBuf> {
-        dirs::cache_dir()
-            .map(|dir| dir.join(APP_NAME))
-            .context("Failed to get cache directory")
-    }
-
-    pub fn update_tokens(&mut self, access_token: String, refresh_token: Option<String>) {
-        self.twitch.access_token = Some(access_token);
-        if let Some(refresh_token) = refresh_token {
-            self.twitch.refresh_token = Some(refresh_token);
-        }
-    }
-
-    pub fn clear_tokens(&mut self) {
-        self.twitch.access_token = None;
-        self.twitch.refresh_token = None;
-    }
-
-    pub fn is_authenticated(&self) -> bool {
-        self.twitch.access_token.is_some()
+    pub async fn save_default(&self, dirs: &AppDirs) -> Result<()> {
+        self.save(&dirs.config_file()).await
     }
 
     pub fn open_stream_url(&self, url: &str) -> Result<()> {
@@ -194,31 +339,110 @@ impl Config {
             info!("Opened stream in default browser: {}", url);
         }
 
-        if let Some(extra_program) = &self.stream_open.extra_command {
-            if !extra_program.trim().is_empty() && !channel_name.is_empty() {
+        if self.has_extra_command()
+            && !channel_name.is_empty()
+            && let Err(e) = self.open_chat(&channel_name)
+        {
+            error!("{e}");
+        }
+
+        Ok(())
+    }
+
+    pub fn open_chat(&self, channel_name: &str) -> Result<()> {
+        match &self.stream_open.extra_command {
+            Some(extra_program) if !extra_program.trim().is_empty() => {
                 let mut extra_args = self.stream_open.extra_arguments.clone();
-                extra_args.push(channel_name.clone());
+                extra_args.push(channel_name.to_string());
 
-                match std::process::Command::new(extra_program)
+                std::process::Command::new(extra_program)
                     .args(&extra_args)
                     .spawn()
-                {
-                    Ok(_) => {
-                        info!(
-                            "Started extra command {}: {} (args: {:?})",
-                            extra_program, channel_name, extra_args
-                        );
-                    }
-                    Err(e) => {
-                        error!("Failed to launch extra command {}: {}", extra_program, e);
-                    }
-                }
+                    .with_context(|| format!("Failed to launch extra command {extra_program}"))?;
+
+                info!(
+                    "Started extra command {}: {} (args: {:?})",
+                    extra_program, channel_name, extra_args
+                );
+            }
+            _ => {
+                let url = format!("https://www.twitch.tv/popout/{channel_name}/chat");
+                webbrowser::open(&url)
+                    .with_context(|| format!("Failed to open chat in default browser: {url}"))?;
+
+                info!("Opened chat in default browser: {}", url);
             }
         }
 
         Ok(())
     }
 
+    fn has_extra_command(&self) -> bool {
+        self.stream_open
+            .extra_command
+            .as_ref()
+            .is_some_and(|program| !program.trim().is_empty())
+    }
+
+    // Configs without an accounts list have one account whose tokens use the default id
+    pub fn accounts(&self) -> Vec<AccountConfig> {
+        if self.twitch.accounts.is_empty() {
+            vec![AccountConfig::new(DEFAULT_ACCOUNT)]
+        } else {
+            self.twitch.accounts.clone()
+        }
+    }
+
+    pub fn is_channel_muted(&self, user_id: &str) -> bool {
+        self.notifications.channel_policy(user_id) == NotificationPolicy::Never
+    }
+
+    pub fn mute_channel(&mut self, user_id: &str) -> bool {
+        self.set_channel_policy(user_id, NotificationPolicy::Never)
+    }
+
+    pub fn set_channel_policy(&mut self, user_id: &str, policy: NotificationPolicy) -> bool {
+        if self.notifications.channel_policy(user_id) == policy {
+            return false;
+        }
+
+        if policy == NotificationPolicy::Default {
+            self.notifications.channel_policies.remove(user_id);
+        } else {
+            self.notifications
+                .channel_policies
+                .insert(user_id.to_string(), policy);
+        }
+        true
+    }
+
+    pub fn is_favourite(&self, broadcaster_id: &str) -> bool {
+        self.ui
+            .favourite_channels
+            .iter()
+            .any(|channel| channel.broadcaster_id == broadcaster_id)
+    }
+
+    pub fn favourite_ids(&self) -> Vec<String> {
+        self.ui
+            .favourite_channels
+            .iter()
+            .map(|channel| channel.broadcaster_id.clone())
+            .collect()
+    }
+
+    pub fn toggle_favourite(&mut self, channel: FavouriteChannel) -> bool {
+        if self.is_favourite(&channel.broadcaster_id) {
+            self.ui
+                .favourite_channels
+                .retain(|c| c.broadcaster_id != channel.broadcaster_id);
+            false
+        } else {
+            self.ui.favourite_channels.push(channel);
+            true
+        }
+    }
+
     fn extract_channel_name(url: &str) -> String {
         if let Some(pos) = url.find("twitch.tv/") {
             let after_domain = &url[pos + 10..];
@@ -0,0 +1,44 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use super::{APP_NAME, CONFIG_FILE};
+
+// Resolved once at startup and passed down, so nothing else reads the environment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AppDirs {
+    pub config: PathBuf,
+    pub cache: PathBuf,
+    pub data: PathBuf,
+    pub autostart: PathBuf,
+}
+
+impl AppDirs {
+    pub fn for_current_user() -> Result<Self> {
+        let config_home = dirs::config_dir().context("Failed to get config directory")?;
+
+        Ok(Self {
+            config: config_home.join(APP_NAME),
+            cache: dirs::cache_dir()
+                .context("Failed to get cache directory")?
+                .join(APP_NAME),
+            data: dirs::data_dir()
+                .context("Failed to get data directory")?
+                .join(APP_NAME),
+            autostart: config_home.join("autostart"),
+        })
+    }
+
+    // Lays the directories out like the XDG defaults, below a single root
+    pub fn under(root: &Path) -> Self {
+        Self {
+            config: root.join("config").join(APP_NAME),
+            cache: root.join("cache").join(APP_NAME),
+            data: root.join("data").join(APP_NAME),
+            autostart: root.join("config").join("autostart"),
+        }
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config.join(CONFIG_FILE)
+    }
+}
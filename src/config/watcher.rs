@@ -0,0 +1,96 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tokio::fs;
+use tracing::debug;
+
+use super::{AppDirs, Config};
+
+pub struct ConfigWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub async fn new(path: PathBuf) -> Self {
+        let modified = Self::modified_time(&path).await;
+        Self { path, modified }
+    }
+
+    pub async fn for_default_config(dirs: &AppDirs) -> Self {
+        Self::new(dirs.config_file()).await
+    }
+
+    pub async fn poll(&mut self) -> Result<Option<Config>> {
+        let modified = Self::modified_time(&self.path).await;
+        if modified.is_none() || modified == self.modified {
+            return Ok(None);
+        }
+
+        // Remember the new timestamp even if parsing fails, the next save will bump it again
+        self.modified = modified;
+        debug!("Config file changed: {:?}", self.path);
+
+        Config::load_from(&self.path).await.map(Some)
+    }
+
+    async fn modified_time(path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).await.and_then(|m| m.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CONFIG_FILE;
+    use std::time::Duration;
+
+    async fn write_config(path: &PathBuf, config: &Config, modified: SystemTime) {
+        config.save(path).await.unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn reports_only_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        let start = SystemTime::now() - Duration::from_secs(60);
+
+        let mut config = Config::default();
+        write_config(&path, &config, start).await;
+
+        let mut watcher = ConfigWatcher::new(path.clone()).await;
+        assert!(watcher.poll().await.unwrap().is_none());
+
+        config.twitch.refresh_interval_minutes = 7;
+        write_config(&path, &config, start + Duration::from_secs(1)).await;
+
+        let reloaded = watcher.poll().await.unwrap().unwrap();
+        assert_eq!(reloaded.twitch.refresh_interval_minutes, 7);
+        assert!(watcher.poll().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn invalid_file_is_reported_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        write_config(&path, &Config::default(), SystemTime::now()).await;
+        let mut watcher = ConfigWatcher::new(path.clone()).await;
+
+        std::fs::write(&path, "not = [valid").unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(1))
+            .unwrap();
+
+        assert!(watcher.poll().await.is_err());
+        assert!(watcher.poll().await.unwrap().is_none());
+    }
+}
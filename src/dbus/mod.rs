@@ -0,0 +1,244 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::{Notify, RwLock};
+use tracing::{debug, info};
+use zbus::object_server::SignalContext;
+use zbus::zvariant::{OwnedValue, Type, Value};
+use zbus::{Connection, connection, fdo, interface};
+
+use crate::api::models::Stream;
+use crate::config::Config;
+
+pub const BUS_NAME: &str = "se.fldc.TwitchIndicator";
+pub const OBJECT_PATH: &str = "/se/fldc/TwitchIndicator";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type, Value, OwnedValue)]
+pub struct LiveStream {
+    pub id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub game_id: String,
+    pub game_name: String,
+    pub title: String,
+    pub viewer_count: u32,
+    pub started_at: String,
+    pub language: String,
+    pub thumbnail_url: String,
+    pub url: String,
+    pub is_mature: bool,
+}
+
+impl From<&Stream> for LiveStream {
+    fn from(stream: &Stream) -> Self {
+        Self {
+            id: stream.id.clone(),
+            user_id: stream.user_id.clone(),
+            user_login: stream.user_login.clone(),
+            user_name: stream.user_name.clone(),
+            game_id: stream.game_id.clone(),
+            game_name: stream.game_name.clone(),
+            title: stream.title.clone(),
+            viewer_count: stream.viewer_count,
+            started_at: stream.started_at.to_rfc3339(),
+            language: stream.language.clone(),
+            thumbnail_url: stream.thumbnail_url.clone(),
+            url: stream.url(),
+            is_mature: stream.is_mature,
+        }
+    }
+}
+
+struct IndicatorInterface {
+    config: Arc<RwLock<Config>>,
+    refresh_requested: Arc<Notify>,
+    streams: Vec<LiveStream>,
+}
+
+#[interface(name = "se.fldc.TwitchIndicator")]
+impl IndicatorInterface {
+    async fn refresh(&self) {
+        info!("Refresh requested over D-Bus");
+        self.refresh_requested.notify_one();
+    }
+
+    async fn open_stream(&self, login: &str) -> fdo::Result<()> {
+        if login.is_empty() || !login.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(fdo::Error::InvalidArgs(format!(
+                "Invalid channel login: {login}"
+            )));
+        }
+
+        let url = format!("https://www.twitch.tv/{}", login.to_lowercase());
+        let config = self.config.read().await;
+        config
+            .open_stream_url(&url)
+            .map_err(|e| fdo::Error::Failed(format!("{e:#}")))
+    }
+
+    #[zbus(property)]
+    async fn live_streams(&self) -> Vec<LiveStream> {
+        self.streams.clone()
+    }
+
+    #[zbus(signal)]
+    async fn stream_went_live(ctxt: &SignalContext<'_>, stream: LiveStream) -> zbus::Result<()>;
+}
+
+pub struct DbusService {
+    connection: Connection,
+}
+
+impl DbusService {
+    pub async fn start(
+        config: Arc<RwLock<Config>>,
+        refresh_requested: Arc<Notify>,
+    ) -> Result<Self> {
+        let builder = connection::Builder::session().context("Failed to connect to session bus")?;
+        Self::start_with(builder, config, refresh_requested).await
+    }
+
+    pub async fn start_with(
+        builder: connection::Builder<'_>,
+        config: Arc<RwLock<Config>>,
+        refresh_requested: Arc<Notify>,
+    ) -> Result<Self> {
+        let iface = IndicatorInterface {
+            config,
+            refresh_requested,
+            streams: Vec::new(),
+        };
+
+        let connection = builder
+            .name(BUS_NAME)?
+            .serve_at(OBJECT_PATH, iface)?
+            .build()
+            .await
+            .with_context(|| format!("Failed to register {BUS_NAME} on the bus"))?;
+
+        info!("D-Bus service registered as {}", BUS_NAME);
+        Ok(Self { connection })
+    }
+
+    pub async fn publish(&self, streams: &[Stream], went_live: &[Stream]) -> Result<()> {
+        let iface_ref = self
+            .connection
+            .object_server()
+            .interface::<_, IndicatorInterface>(OBJECT_PATH)
+            .await?;
+
+        {
+            let mut iface = iface_ref.get_mut().await;
+            iface.streams = streams.iter().map(LiveStream::from).collect();
+            iface
+                .live_streams_changed(iface_ref.signal_context())
+                .await?;
+        }
+
+        for stream in went_live {
+            IndicatorInterface::stream_went_live(iface_ref.signal_context(), stream.into()).await?;
+        }
+
+        debug!(
+            "Published {} live streams over D-Bus ({} went live)",
+            streams.len(),
+            went_live.len()
+        );
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::PrivateBus;
+    use crate::testing::fake_twitch::stream;
+    use futures_util::StreamExt;
+    use std::time::Duration;
+
+    async fn start_service(
+        bus: &PrivateBus,
+        refresh: Arc<Notify>,
+    ) -> (DbusService, zbus::Proxy<'static>) {
+        let config = Arc::new(RwLock::new(Config::default()));
+        let builder = connection::Builder::address(bus.address().as_str()).unwrap();
+        let service = DbusService::start_with(builder, config, refresh)
+            .await
+            .unwrap();
+
+        let client = connection::Builder::address(bus.address().as_str())
+            .unwrap()
+            .build()
+            .await
+            .unwrap();
+        let proxy = zbus::proxy::Builder::new(&client)
+            .destination(BUS_NAME)
+            .unwrap()
+            .path(OBJECT_PATH)
+            .unwrap()
+            .interface(BUS_NAME)
+            .unwrap()
+            .cache_properties(zbus::CacheProperties::No)
+            .build()
+            .await
+            .unwrap();
+
+        (service, proxy)
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon, run with --ignored"]
+    async fn publishes_live_streams_and_went_live_signal() {
+        let bus = PrivateBus::start();
+        let (service, proxy) = start_service(&bus, Arc::new(Notify::new())).await;
+        let mut went_live = proxy.receive_signal("StreamWentLive").await.unwrap();
+
+        let streams = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 20),
+        ];
+        service.publish(&streams, &streams[1..]).await.unwrap();
+
+        let live: Vec<LiveStream> = proxy.get_property("LiveStreams").await.unwrap();
+        assert_eq!(live.len(), 2);
+        assert_eq!(live[0].user_login, "alpha");
+        assert_eq!(live[1].url, "https://www.twitch.tv/beta");
+
+        let signal = tokio::time::timeout(Duration::from_secs(5), went_live.next())
+            .await
+            .unwrap()
+            .unwrap();
+        let (stream,): (LiveStream,) = signal.body().deserialize().unwrap();
+        assert_eq!(stream.user_login, "beta");
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon, run with --ignored"]
+    async fn refresh_method_wakes_poll_loop() {
+        let bus = PrivateBus::start();
+        let refresh = Arc::new(Notify::new());
+        let (_service, proxy) = start_service(&bus, refresh.clone()).await;
+
+        proxy.call_method("Refresh", &()).await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), refresh.notified())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[ignore = "needs dbus-daemon, run with --ignored"]
+    async fn open_stream_rejects_invalid_login() {
+        let bus = PrivateBus::start();
+        let (_service, proxy) = start_service(&bus, Arc::new(Notify::new())).await;
+
+        let err = proxy
+            .call_method("OpenStream", &("../evil",))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(err, zbus::Error::MethodError(name, _, _) if name.as_str() == "org.freedesktop.DBus.Error.InvalidArgs")
+        );
+    }
+}
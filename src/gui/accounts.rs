@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use crate::api::error::is_transient;
+use crate::api::{Stream, TwitchApiError, TwitchClient, User};
+use crate::config::{AccountConfig, Config, CredentialStore};
+
+pub struct Account {
+    pub config: AccountConfig,
+    pub client: TwitchClient,
+    pub user: Option<User>,
+    // Live streams from the last successful update, kept while updates fail
+    pub streams: Vec<Stream>,
+    pub error: Option<String>,
+    // Set when Twitch refused the token and renewing it failed, so only a new sign-in helps
+    pub rejected: bool,
+}
+
+impl Account {
+    pub async fn open(
+        config: AccountConfig,
+        app_config: Arc<RwLock<Config>>,
+        credentials: Arc<CredentialStore>,
+    ) -> Result<Self> {
+        let twitch_config = app_config.read().await.twitch.clone();
+        let mut client = TwitchClient::new(&twitch_config, app_config, credentials, &config.id);
+        client.load_tokens().await?;
+
+        Ok(Self {
+            config,
+            client,
+            user: None,
+            streams: Vec::new(),
+            error: None,
+            rejected: false,
+        })
+    }
+
+    // Falls back to the login saved in the config until the user has been fetched
+    pub fn name(&self) -> &str {
+        match &self.user {
+            Some(user) => &user.display_name,
+            None if !self.config.login.is_empty() => &self.config.login,
+            None => &self.config.id,
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.config.id == name
+            || self.config.login.eq_ignore_ascii_case(name)
+            || self
+                .user
+                .as_ref()
+                .is_some_and(|user| user.login.eq_ignore_ascii_case(name))
+    }
+
+    pub async fn load_user(&mut self) -> Result<&User> {
+        let user = match self.client.get_user().await {
+            Ok(user) => user,
+            Err(e) => {
+                let e = e.context(format!("Failed to get user info for {}", self.name()));
+                self.record_error(&e);
+                return Err(e);
+            }
+        };
+
+        info!("Authenticated as: {} ({})", user.display_name, user.login);
+        self.clear_error();
+        Ok(self.user.insert(user))
+    }
+
+    pub fn record_error(&mut self, err: &anyhow::Error) {
+        self.error = Some(format!("{err:#}"));
+        self.rejected = matches!(
+            TwitchApiError::find(err),
+            Some(TwitchApiError::Unauthorized)
+        );
+    }
+
+    fn clear_error(&mut self) {
+        self.error = None;
+        self.rejected = false;
+    }
+
+    // The outcome is kept on the account, so a failing account doesn't hide the others
+    pub async fn update_streams(&mut self) -> Result<()> {
+        // A sign-in from the tray or --add-account stores new tokens for the account
+        if self.rejected
+            && let Err(e) = self.client.load_tokens().await
+        {
+            warn!("{:#}", e);
+        }
+
+        let result = self.fetch_followed_streams().await;
+        match result {
+            Ok(streams) => {
+                self.streams = streams;
+                self.clear_error();
+                Ok(())
+            }
+            // Clearing the streams would report them all as ended, and as live again on recovery
+            Err(e) => {
+                self.record_error(&e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn fetch_followed_streams(&mut self) -> Result<Vec<Stream>> {
+        let user_id = match &self.user {
+            Some(user) => user.id.clone(),
+            None => self.load_user().await?.id.clone(),
+        };
+
+        self.client
+            .get_followed_streams(&user_id)
+            .await
+            .with_context(|| format!("Failed to get followed streams for {}", self.name()))
+    }
+
+    // Stored tokens are kept when Twitch is unreachable, and replaced only when rejected
+    pub async fn sign_in(&mut self) -> Result<()> {
+        if !self.client.has_access_token().await {
+            info!(
+                "Account {} not authenticated, starting authentication flow",
+                self.name()
+            );
+            return self.authenticate().await;
+        }
+
+        info!(
+            "Account {} already authenticated, validating token",
+            self.name()
+        );
+        match self.validate_and_refresh_token().await {
+            Ok(()) => Ok(()),
+            Err(e) if is_transient(&e) => {
+                warn!("Could not reach Twitch to validate token: {:#}", e);
+                Ok(())
+            }
+            Err(e) => {
+                warn!("Token validation failed: {}, re-authenticating", e);
+                self.authenticate().await
+            }
+        }
+    }
+
+    pub async fn authenticate(&mut self) -> Result<()> {
+        info!("Starting Twitch authentication");
+
+        self.client
+            .authenticate()
+            .await
+            .context("Authentication failed")?;
+
+        info!("Authentication completed successfully");
+        self.clear_error();
+        Ok(())
+    }
+
+    async fn validate_and_refresh_token(&mut self) -> Result<()> {
+        match self.client.validate_token().await {
+            Ok(validation) => {
+                debug!("Token valid for user: {}", validation.login);
+                Ok(())
+            }
+            Err(e) if is_transient(&e) => Err(e),
+            Err(e) if self.client.has_refresh_token().await => {
+                info!(
+                    "Token validation failed ({}), renewing with refresh token",
+                    e
+                );
+                self.client.refresh_access_token().await?;
+                self.client.validate_token().await.map(|_| ())
+            }
+            Err(e) => {
+                info!("Token validation failed, re-authentication required");
+                Err(e)
+            }
+        }
+    }
+}
+
+// Channels followed by several accounts are listed once. Followers are only
+// named when there is more than one account, and a stream is notified when
+// any of its followers has notifications enabled.
+pub fn merge_followed_streams(accounts: &[Account]) -> (Vec<Stream>, Vec<Stream>) {
+    let tag_followers = accounts.len() > 1;
+    let mut merged: Vec<Stream> = Vec::new();
+    let mut notifiable = HashSet::new();
+
+    for account in accounts {
+        for stream in &account.streams {
+            if account.config.notifications {
+                notifiable.insert(stream.id.clone());
+            }
+
+            let index = match merged.iter().position(|s| s.id == stream.id) {
+                Some(index) => index,
+                None => {
+                    merged.push(stream.clone());
+                    merged.len() - 1
+                }
+            };
+            if tag_followers {
+                merged[index].followed_by.push(account.name().to_string());
+            }
+        }
+    }
+
+    let notified = merged
+        .iter()
+        .filter(|s| notifiable.contains(&s.id))
+        .cloned()
+        .collect();
+    (merged, notified)
+}
@@ -1,21 +1,29 @@
 #![allow(dead_code)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use gtk::glib::Propagation;
 use gtk::prelude::*;
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::info;
 
-use crate::config::Config;
+use crate::api::TwitchClient;
+use crate::config::{
+    AppDirs, Autostart, Config, CredentialStore, FavouriteChannel, MenuLayout, NotificationRule,
+    RuleAction, TrayBadge,
+};
+use crate::gui::notification_filter::compile_title_regex;
 
 pub struct GtkSettingsWindow {
     config: Arc<RwLock<Config>>,
+    dirs: AppDirs,
     temp_config: Config,
 }
 
 impl GtkSettingsWindow {
-    pub async fn new(config: Arc<RwLock<Config>>) -> Result<Self> {
+    pub async fn new(config: Arc<RwLock<Config>>, dirs: AppDirs) -> Result<Self> {
         let temp_config = {
             let config_guard = config.read().await;
             config_guard.clone()
@@ -23,6 +31,7 @@ impl GtkSettingsWindow {
 
         Ok(GtkSettingsWindow {
             config,
+            dirs,
             temp_config,
         })
     }
@@ -94,6 +103,135 @@ impl GtkSettingsWindow {
         show_viewers_check.set_active(self.temp_config.notifications.show_viewer_count);
         notifications_box.pack_start(&show_viewers_check, false, false, 0);
 
+        let show_uptime_check = gtk::CheckButton::with_label("Show stream uptime in notifications");
+        show_uptime_check.set_active(self.temp_config.notifications.show_uptime);
+        notifications_box.pack_start(&show_uptime_check, false, false, 0);
+
+        let category_change_check =
+            gtk::CheckButton::with_label("Notify when a live stream changes category");
+        category_change_check.set_active(self.temp_config.notifications.notify_category_change);
+        notifications_box.pack_start(&category_change_check, false, false, 0);
+
+        let title_change_check =
+            gtk::CheckButton::with_label("Notify when a live stream changes title");
+        title_change_check.set_active(self.temp_config.notifications.notify_title_change);
+        notifications_box.pack_start(&title_change_check, false, false, 0);
+
+        let offline_check = gtk::CheckButton::with_label("Notify when a stream goes offline");
+        offline_check.set_active(self.temp_config.notifications.notify_offline);
+        notifications_box.pack_start(&offline_check, false, false, 0);
+
+        let rules = Rc::new(RefCell::new(self.temp_config.notifications.rules.clone()));
+        let rules_label = gtk::Label::new(Some(
+            "Filter rules (exclude rules win; with include rules a stream must match one):",
+        ));
+        rules_label.set_halign(gtk::Align::Start);
+        rules_label.set_line_wrap(true);
+        notifications_box.pack_start(&rules_label, false, false, 0);
+
+        let rules_list = gtk::ListBox::new();
+        rules_list.set_selection_mode(gtk::SelectionMode::None);
+        Self::populate_rules(&rules_list, &rules);
+
+        let rules_scroll = gtk::ScrolledWindow::builder()
+            .min_content_height(100)
+            .child(&rules_list)
+            .build();
+        notifications_box.pack_start(&rules_scroll, true, true, 0);
+
+        let rule_grid = gtk::Grid::new();
+        rule_grid.set_row_spacing(5);
+        rule_grid.set_column_spacing(10);
+
+        let rule_action_combo = gtk::ComboBoxText::new();
+        rule_action_combo.append(Some("include"), "Include");
+        rule_action_combo.append(Some("exclude"), "Exclude");
+        rule_action_combo.set_active_id(Some("include"));
+
+        let rule_game_entry = gtk::Entry::new();
+        rule_game_entry.set_placeholder_text(Some("Game name or id"));
+        let rule_title_entry = gtk::Entry::new();
+        rule_title_entry.set_placeholder_text(Some("Title regex, e.g. tournament"));
+        let rule_language_entry = gtk::Entry::new();
+        rule_language_entry.set_placeholder_text(Some("Language, e.g. en"));
+
+        let rule_mature_combo = gtk::ComboBoxText::new();
+        rule_mature_combo.append(Some("any"), "Any audience");
+        rule_mature_combo.append(Some("mature"), "Mature only");
+        rule_mature_combo.append(Some("not_mature"), "Not mature");
+        rule_mature_combo.set_active_id(Some("any"));
+
+        let rule_viewers_label = gtk::Label::new(Some("Min viewers:"));
+        let rule_viewers_spin = gtk::SpinButton::with_range(0.0, 1_000_000.0, 10.0);
+        let add_rule_button = gtk::Button::with_label("Add rule");
+        let rule_error_label = gtk::Label::new(None);
+        rule_error_label.set_halign(gtk::Align::Start);
+
+        rule_grid.attach(&rule_action_combo, 0, 0, 1, 1);
+        rule_grid.attach(&rule_game_entry, 1, 0, 1, 1);
+        rule_grid.attach(&rule_title_entry, 2, 0, 1, 1);
+        rule_grid.attach(&rule_language_entry, 0, 1, 1, 1);
+        rule_grid.attach(&rule_mature_combo, 1, 1, 1, 1);
+        rule_grid.attach(&rule_viewers_label, 0, 2, 1, 1);
+        rule_grid.attach(&rule_viewers_spin, 1, 2, 1, 1);
+        rule_grid.attach(&add_rule_button, 2, 2, 1, 1);
+        notifications_box.pack_start(&rule_grid, false, false, 0);
+        notifications_box.pack_start(&rule_error_label, false, false, 0);
+
+        let rules_for_add = rules.clone();
+        let rules_list_for_add = rules_list.clone();
+        add_rule_button.connect_clicked(move |_| {
+            let game = rule_game_entry.text().trim().to_string();
+            let title_regex = rule_title_entry.text().trim().to_string();
+            let language = rule_language_entry.text().trim().to_string();
+            let min_viewers = rule_viewers_spin.value() as u32;
+
+            if !title_regex.is_empty()
+                && let Err(e) = compile_title_regex(&title_regex)
+            {
+                rule_error_label.set_text(&format!("Invalid title regex: {e}"));
+                return;
+            }
+
+            let is_game_id = !game.is_empty() && game.chars().all(|c| c.is_ascii_digit());
+            let rule = NotificationRule {
+                action: match rule_action_combo.active_id().as_deref() {
+                    Some("exclude") => RuleAction::Exclude,
+                    _ => RuleAction::Include,
+                },
+                game_name: (!game.is_empty() && !is_game_id).then(|| game.clone()),
+                game_id: is_game_id.then(|| game.clone()),
+                title_regex: (!title_regex.is_empty()).then_some(title_regex),
+                language: (!language.is_empty()).then_some(language),
+                is_mature: match rule_mature_combo.active_id().as_deref() {
+                    Some("mature") => Some(true),
+                    Some("not_mature") => Some(false),
+                    _ => None,
+                },
+                min_viewers: (min_viewers > 0).then_some(min_viewers),
+            };
+
+            if rule
+                == (NotificationRule {
+                    action: rule.action,
+                    ..Default::default()
+                })
+            {
+                rule_error_label.set_text("A rule needs at least one condition");
+                return;
+            }
+
+            rule_error_label.set_text("");
+            rule_game_entry.set_text("");
+            rule_title_entry.set_text("");
+            rule_language_entry.set_text("");
+            rule_mature_combo.set_active_id(Some("any"));
+            rule_viewers_spin.set_value(0.0);
+
+            rules_for_add.borrow_mut().push(rule);
+            Self::populate_rules(&rules_list_for_add, &rules_for_add);
+        });
+
         let ui_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
         ui_box.set_margin_start(10);
         ui_box.set_margin_end(10);
@@ -108,6 +246,121 @@ impl GtkSettingsWindow {
         dark_theme_check.set_active(self.temp_config.ui.dark_theme);
         ui_box.pack_start(&dark_theme_check, false, false, 0);
 
+        let group_by_game_check = gtk::CheckButton::with_label("Group live streams by game");
+        group_by_game_check.set_active(self.temp_config.ui.menu_layout == MenuLayout::ByGame);
+        ui_box.pack_start(&group_by_game_check, false, false, 0);
+
+        let tray_uptime_check = gtk::CheckButton::with_label("Show stream uptime in the tray");
+        tray_uptime_check.set_active(self.temp_config.ui.show_uptime);
+        ui_box.pack_start(&tray_uptime_check, false, false, 0);
+
+        let recently_ended_box = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+        let recently_ended_label = gtk::Label::new(Some("Show recently ended streams (hours):"));
+        let recently_ended_spin = gtk::SpinButton::with_range(0.0, 48.0, 1.0);
+        recently_ended_spin.set_value(self.temp_config.ui.recently_ended_hours as f64);
+
+        recently_ended_box.pack_start(&recently_ended_label, false, false, 0);
+        recently_ended_box.pack_start(&recently_ended_spin, false, false, 0);
+        ui_box.pack_start(&recently_ended_box, false, false, 0);
+
+        let badge_box = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+        let badge_label = gtk::Label::new(Some("Tray icon badge:"));
+        let badge_combo = gtk::ComboBoxText::new();
+        badge_combo.append(Some("live"), "Live streams");
+        badge_combo.append(Some("favourites"), "Live favourites");
+        badge_combo.append(Some("off"), "No badge");
+        badge_combo.set_active_id(Some(match self.temp_config.ui.tray_badge {
+            TrayBadge::Live => "live",
+            TrayBadge::Favourites => "favourites",
+            TrayBadge::Off => "off",
+        }));
+
+        badge_box.pack_start(&badge_label, false, false, 0);
+        badge_box.pack_start(&badge_combo, false, false, 0);
+        ui_box.pack_start(&badge_box, false, false, 0);
+
+        let favourites = Rc::new(RefCell::new(self.temp_config.ui.favourite_channels.clone()));
+        // What the list held when last saved, so only this window's edits are applied
+        let saved_favourites =
+            Rc::new(RefCell::new(self.temp_config.ui.favourite_channels.clone()));
+        let favourites_label = gtk::Label::new(Some("Favourite channels:"));
+        favourites_label.set_halign(gtk::Align::Start);
+        ui_box.pack_start(&favourites_label, false, false, 0);
+
+        let favourites_list = gtk::ListBox::new();
+        favourites_list.set_selection_mode(gtk::SelectionMode::None);
+        Self::populate_favourites(&favourites_list, &favourites);
+
+        let favourites_scroll = gtk::ScrolledWindow::builder()
+            .min_content_height(120)
+            .child(&favourites_list)
+            .build();
+        ui_box.pack_start(&favourites_scroll, true, true, 0);
+
+        let add_favourite_box = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+        let add_favourite_entry = gtk::Entry::new();
+        add_favourite_entry.set_placeholder_text(Some("Channel name"));
+        let add_favourite_button = gtk::Button::with_label("Add");
+        add_favourite_box.pack_start(&add_favourite_entry, true, true, 0);
+        add_favourite_box.pack_start(&add_favourite_button, false, false, 0);
+        ui_box.pack_start(&add_favourite_box, false, false, 0);
+
+        let favourite_error_label = gtk::Label::new(None);
+        favourite_error_label.set_halign(gtk::Align::Start);
+        ui_box.pack_start(&favourite_error_label, false, false, 0);
+
+        let favourites_for_add = favourites.clone();
+        let favourites_list_for_add = favourites_list.clone();
+        let config_for_add = self.config.clone();
+        let dirs_for_add = self.dirs.clone();
+        add_favourite_button.connect_clicked(move |button| {
+            let login = add_favourite_entry
+                .text()
+                .trim()
+                .trim_start_matches('@')
+                .to_lowercase();
+            if login.is_empty() {
+                return;
+            }
+
+            button.set_sensitive(false);
+            favourite_error_label.set_text("");
+
+            // Looked up off the GTK thread; the window picks the result up when it arrives
+            let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+            let config = config_for_add.clone();
+            let dirs = dirs_for_add.clone();
+            std::thread::spawn(move || {
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                let _ = result_tx.send(rt.block_on(Self::find_channel(config, dirs, login)));
+            });
+
+            let button = button.clone();
+            let entry = add_favourite_entry.clone();
+            let error_label = favourite_error_label.clone();
+            let favourites = favourites_for_add.clone();
+            let list = favourites_list_for_add.clone();
+            gtk::glib::MainContext::default().spawn_local(async move {
+                let result = result_rx.await;
+                button.set_sensitive(true);
+                match result {
+                    Ok(Ok(channel)) => {
+                        entry.set_text("");
+                        let known = favourites
+                            .borrow()
+                            .iter()
+                            .any(|c| c.broadcaster_id == channel.broadcaster_id);
+                        if !known {
+                            favourites.borrow_mut().push(channel);
+                            Self::populate_favourites(&list, &favourites);
+                        }
+                    }
+                    Ok(Err(e)) => error_label.set_text(&format!("{e:#}")),
+                    Err(_) => error_label.set_text("Channel lookup was interrupted"),
+                }
+            });
+        });
+
         let stream_box = gtk::Box::new(gtk::Orientation::Vertical, 10);
         stream_box.set_margin_start(10);
         stream_box.set_margin_end(10);
@@ -218,12 +471,23 @@ impl GtkSettingsWindow {
         let notify_enabled_clone = notify_enabled.clone();
         let show_game_check_clone = show_game_check.clone();
         let show_viewers_check_clone = show_viewers_check.clone();
+        let show_uptime_check_clone = show_uptime_check.clone();
+        let category_change_check_clone = category_change_check.clone();
+        let title_change_check_clone = title_change_check.clone();
+        let offline_check_clone = offline_check.clone();
+        let recently_ended_spin_clone = recently_ended_spin.clone();
+        let badge_combo_clone = badge_combo.clone();
+        let group_by_game_check_clone = group_by_game_check.clone();
+        let tray_uptime_check_clone = tray_uptime_check.clone();
         let top_channels_check_clone = top_channels_check.clone();
         let dark_theme_check_clone = dark_theme_check.clone();
         let program_entry_clone = program_entry.clone();
         let args_entry_clone = args_entry.clone();
         let extra_prog_entry_clone = extra_prog_entry.clone();
         let extra_args_entry_clone = extra_args_entry.clone();
+        let favourites_clone = favourites.clone();
+        let saved_favourites_clone = saved_favourites.clone();
+        let rules_clone = rules.clone();
 
         let window_clone = window.clone();
         cancel_button.connect_clicked(move |_| {
@@ -231,8 +495,10 @@ impl GtkSettingsWindow {
         });
 
         let apply_config = config_arc.clone();
+        let apply_dirs = self.dirs.clone();
         apply_button.connect_clicked(move |_| {
             let config = apply_config.clone();
+            let dirs = apply_dirs.clone();
             let interval = interval_spin_clone.value() as u64;
             let timeout = timeout_spin_clone.value() as u32;
             let autostart = autostart_check_clone.is_active();
@@ -240,17 +506,35 @@ impl GtkSettingsWindow {
             let notify_enabled = notify_enabled_clone.is_active();
             let show_game = show_game_check_clone.is_active();
             let show_viewers = show_viewers_check_clone.is_active();
+            let show_uptime = show_uptime_check_clone.is_active();
+            let category_change = category_change_check_clone.is_active();
+            let title_change = title_change_check_clone.is_active();
+            let notify_offline = offline_check_clone.is_active();
+            let recently_ended_hours = recently_ended_spin_clone.value() as u64;
+            let tray_badge = Self::selected_tray_badge(&badge_combo_clone);
+            let group_by_game = group_by_game_check_clone.is_active();
+            let tray_uptime = tray_uptime_check_clone.is_active();
             let top_channels = top_channels_check_clone.is_active();
             let dark_theme = dark_theme_check_clone.is_active();
             let program_text = program_entry_clone.text();
             let args_text = args_entry_clone.text();
             let extra_prog_text = extra_prog_entry_clone.text();
             let extra_args_text = extra_args_entry_clone.text();
+            let favourite_channels = favourites_clone.borrow().clone();
+            let previous_favourites = saved_favourites_clone.replace(favourite_channels.clone());
+            let notification_rules = rules_clone.borrow().clone();
 
             std::thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new().unwrap();
                 rt.block_on(async {
                     if let Ok(mut config_guard) = config.try_write() {
+                        // The indicator saves favourite toggles and mutes while this window
+                        // is open, so start from the file rather than the snapshot
+                        match Config::load_from(&dirs.config_file()).await {
+                            Ok(saved) => *config_guard = saved,
+                            Err(e) => eprintln!("Failed to reload settings before saving: {e}"),
+                        }
+
                         config_guard.twitch.refresh_interval_minutes = interval;
                         config_guard.notifications.timeout_ms = timeout;
                         config_guard.general.autostart = autostart;
@@ -258,8 +542,26 @@ impl GtkSettingsWindow {
                         config_guard.notifications.enabled = notify_enabled;
                         config_guard.notifications.show_game = show_game;
                         config_guard.notifications.show_viewer_count = show_viewers;
+                        config_guard.notifications.show_uptime = show_uptime;
+                        config_guard.notifications.notify_category_change = category_change;
+                        config_guard.notifications.notify_title_change = title_change;
+                        config_guard.notifications.notify_offline = notify_offline;
+                        config_guard.ui.recently_ended_hours = recently_ended_hours;
+                        config_guard.ui.tray_badge = tray_badge;
+                        config_guard.ui.menu_layout = if group_by_game {
+                            MenuLayout::ByGame
+                        } else {
+                            MenuLayout::Flat
+                        };
+                        config_guard.ui.show_uptime = tray_uptime;
+                        config_guard.notifications.rules = notification_rules;
                         config_guard.ui.show_selected_channels_on_top = top_channels;
                         config_guard.ui.dark_theme = dark_theme;
+                        config_guard.ui.favourite_channels = Self::merge_favourites(
+                            &config_guard.ui.favourite_channels,
+                            &previous_favourites,
+                            &favourite_channels,
+                        );
 
                         config_guard.stream_open.program = if program_text.is_empty() {
                             None
@@ -288,8 +590,10 @@ impl GtkSettingsWindow {
                                 .collect()
                         };
 
-                        if let Err(e) = config_guard.save_default().await {
+                        if let Err(e) = config_guard.save_default(&dirs).await {
                             eprintln!("Failed to save settings: {e}");
+                        } else if let Err(e) = Self::apply_autostart(&dirs, autostart).await {
+                            eprintln!("Failed to update autostart: {e}");
                         } else {
                             println!("Settings applied successfully");
                         }
@@ -299,9 +603,11 @@ impl GtkSettingsWindow {
         });
 
         let ok_config = config_arc.clone();
+        let ok_dirs = self.dirs.clone();
         let window_clone2 = window.clone();
         ok_button.connect_clicked(move |_| {
             let config = ok_config.clone();
+            let dirs = ok_dirs.clone();
             let interval = interval_spin.value() as u64;
             let timeout = timeout_spin.value() as u32;
             let autostart = autostart_check.is_active();
@@ -309,17 +615,35 @@ impl GtkSettingsWindow {
             let notify_enabled = notify_enabled.is_active();
             let show_game = show_game_check.is_active();
             let show_viewers = show_viewers_check.is_active();
+            let show_uptime = show_uptime_check.is_active();
+            let category_change = category_change_check.is_active();
+            let title_change = title_change_check.is_active();
+            let notify_offline = offline_check.is_active();
+            let recently_ended_hours = recently_ended_spin.value() as u64;
+            let tray_badge = Self::selected_tray_badge(&badge_combo);
+            let group_by_game = group_by_game_check.is_active();
+            let tray_uptime = tray_uptime_check.is_active();
             let top_channels = top_channels_check.is_active();
             let dark_theme = dark_theme_check.is_active();
             let program_text = program_entry.text();
             let args_text = args_entry.text();
             let extra_prog_text = extra_prog_entry.text();
             let extra_args_text = extra_args_entry.text();
+            let favourite_channels = favourites.borrow().clone();
+            let previous_favourites = saved_favourites.replace(favourite_channels.clone());
+            let notification_rules = rules.borrow().clone();
 
             std::thread::spawn(move || {
                 let rt = tokio::runtime::Runtime::new().unwrap();
                 rt.block_on(async {
                     if let Ok(mut config_guard) = config.try_write() {
+                        // The indicator saves favourite toggles and mutes while this window
+                        // is open, so start from the file rather than the snapshot
+                        match Config::load_from(&dirs.config_file()).await {
+                            Ok(saved) => *config_guard = saved,
+                            Err(e) => eprintln!("Failed to reload settings before saving: {e}"),
+                        }
+
                         config_guard.twitch.refresh_interval_minutes = interval;
                         config_guard.notifications.timeout_ms = timeout;
                         config_guard.general.autostart = autostart;
@@ -327,8 +651,26 @@ impl GtkSettingsWindow {
                         config_guard.notifications.enabled = notify_enabled;
                         config_guard.notifications.show_game = show_game;
                         config_guard.notifications.show_viewer_count = show_viewers;
+                        config_guard.notifications.show_uptime = show_uptime;
+                        config_guard.notifications.notify_category_change = category_change;
+                        config_guard.notifications.notify_title_change = title_change;
+                        config_guard.notifications.notify_offline = notify_offline;
+                        config_guard.ui.recently_ended_hours = recently_ended_hours;
+                        config_guard.ui.tray_badge = tray_badge;
+                        config_guard.ui.menu_layout = if group_by_game {
+                            MenuLayout::ByGame
+                        } else {
+                            MenuLayout::Flat
+                        };
+                        config_guard.ui.show_uptime = tray_uptime;
+                        config_guard.notifications.rules = notification_rules;
                         config_guard.ui.show_selected_channels_on_top = top_channels;
                         config_guard.ui.dark_theme = dark_theme;
+                        config_guard.ui.favourite_channels = Self::merge_favourites(
+                            &config_guard.ui.favourite_channels,
+                            &previous_favourites,
+                            &favourite_channels,
+                        );
 
                         config_guard.stream_open.program = if program_text.is_empty() {
                             None
@@ -357,8 +699,10 @@ impl GtkSettingsWindow {
                                 .collect()
                         };
 
-                        if let Err(e) = config_guard.save_default().await {
+                        if let Err(e) = config_guard.save_default(&dirs).await {
                             eprintln!("Failed to save settings: {e}");
+                        } else if let Err(e) = Self::apply_autostart(&dirs, autostart).await {
+                            eprintln!("Failed to update autostart: {e}");
                         } else {
                             println!("Settings saved and applied");
                         }
@@ -381,4 +725,197 @@ impl GtkSettingsWindow {
 
         Ok(())
     }
+
+    fn selected_tray_badge(combo: &gtk::ComboBoxText) -> TrayBadge {
+        match combo.active_id().as_deref() {
+            Some("favourites") => TrayBadge::Favourites,
+            Some("off") => TrayBadge::Off,
+            _ => TrayBadge::Live,
+        }
+    }
+
+    // Favourites are keyed by broadcaster id, so a typed name is resolved with the
+    // first account's token
+    async fn find_channel(
+        config: Arc<RwLock<Config>>,
+        dirs: AppDirs,
+        login: String,
+    ) -> Result<FavouriteChannel> {
+        let (twitch_config, account) = {
+            let config = config.read().await;
+            (config.twitch.clone(), config.accounts().remove(0))
+        };
+
+        let credentials = CredentialStore::open_default(&dirs).await?;
+        let mut client =
+            TwitchClient::new(&twitch_config, config, Arc::new(credentials), &account.id);
+        client.load_tokens().await?;
+
+        let user = client
+            .get_user_by_login(&login)
+            .await?
+            .with_context(|| format!("No Twitch channel named {login}"))?;
+        Ok(FavouriteChannel {
+            broadcaster_id: user.id,
+            broadcaster_name: user.display_name,
+        })
+    }
+
+    // Applies the channels added and removed here to the saved list, keeping changes
+    // made from the tray meanwhile
+    fn merge_favourites(
+        current: &[FavouriteChannel],
+        previous: &[FavouriteChannel],
+        edited: &[FavouriteChannel],
+    ) -> Vec<FavouriteChannel> {
+        let contains = |list: &[FavouriteChannel], channel: &FavouriteChannel| {
+            list.iter()
+                .any(|c| c.broadcaster_id == channel.broadcaster_id)
+        };
+
+        let mut merged: Vec<FavouriteChannel> = current
+            .iter()
+            .filter(|c| !contains(previous, c) || contains(edited, c))
+            .cloned()
+            .collect();
+        for channel in edited {
+            if !contains(previous, channel) && !contains(&merged, channel) {
+                merged.push(channel.clone());
+            }
+        }
+        merged
+    }
+
+    fn populate_favourites(list: &gtk::ListBox, favourites: &Rc<RefCell<Vec<FavouriteChannel>>>) {
+        for row in list.children() {
+            list.remove(&row);
+        }
+
+        if favourites.borrow().is_empty() {
+            let empty_label = gtk::Label::new(Some("No favourite channels"));
+            empty_label.set_sensitive(false);
+            list.add(&empty_label);
+        }
+
+        for channel in favourites.borrow().iter() {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+            let name_label = gtk::Label::new(Some(&channel.broadcaster_name));
+            name_label.set_halign(gtk::Align::Start);
+            let remove_button = gtk::Button::with_label("Remove");
+
+            row.pack_start(&name_label, true, true, 0);
+            row.pack_end(&remove_button, false, false, 0);
+            list.add(&row);
+
+            let list_clone = list.clone();
+            let favourites_clone = favourites.clone();
+            let broadcaster_id = channel.broadcaster_id.clone();
+            remove_button.connect_clicked(move |_| {
+                favourites_clone
+                    .borrow_mut()
+                    .retain(|c| c.broadcaster_id != broadcaster_id);
+                Self::populate_favourites(&list_clone, &favourites_clone);
+            });
+        }
+
+        list.show_all();
+    }
+
+    fn populate_rules(list: &gtk::ListBox, rules: &Rc<RefCell<Vec<NotificationRule>>>) {
+        for row in list.children() {
+            list.remove(&row);
+        }
+
+        if rules.borrow().is_empty() {
+            let empty_label = gtk::Label::new(Some("No rules, notify for every stream"));
+            empty_label.set_sensitive(false);
+            list.add(&empty_label);
+        }
+
+        for (index, rule) in rules.borrow().iter().enumerate() {
+            let row = gtk::Box::new(gtk::Orientation::Horizontal, 10);
+            let rule_label = gtk::Label::new(Some(&Self::describe_rule(rule)));
+            rule_label.set_halign(gtk::Align::Start);
+            rule_label.set_line_wrap(true);
+            let remove_button = gtk::Button::with_label("Remove");
+
+            row.pack_start(&rule_label, true, true, 0);
+            row.pack_end(&remove_button, false, false, 0);
+            list.add(&row);
+
+            let list_clone = list.clone();
+            let rules_clone = rules.clone();
+            remove_button.connect_clicked(move |_| {
+                rules_clone.borrow_mut().remove(index);
+                Self::populate_rules(&list_clone, &rules_clone);
+            });
+        }
+
+        list.show_all();
+    }
+
+    fn describe_rule(rule: &NotificationRule) -> String {
+        let mut conditions = Vec::new();
+
+        if let Some(game_name) = &rule.game_name {
+            conditions.push(format!("game is {game_name}"));
+        }
+        if let Some(game_id) = &rule.game_id {
+            conditions.push(format!("game id is {game_id}"));
+        }
+        if let Some(title_regex) = &rule.title_regex {
+            conditions.push(format!("title matches /{title_regex}/"));
+        }
+        if let Some(language) = &rule.language {
+            conditions.push(format!("language is {language}"));
+        }
+        match rule.is_mature {
+            Some(true) => conditions.push("mature".to_string()),
+            Some(false) => conditions.push("not mature".to_string()),
+            None => {}
+        }
+        if let Some(min_viewers) = rule.min_viewers {
+            conditions.push(format!("at least {min_viewers} viewers"));
+        }
+
+        let action = match rule.action {
+            RuleAction::Include => "Include",
+            RuleAction::Exclude => "Exclude",
+        };
+
+        format!("{action} when {}", conditions.join(", "))
+    }
+
+    async fn apply_autostart(dirs: &AppDirs, enabled: bool) -> Result<()> {
+        Autostart::for_current_exe(dirs)?.set_enabled(enabled).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(ids: &[&str]) -> Vec<FavouriteChannel> {
+        ids.iter()
+            .map(|id| FavouriteChannel {
+                broadcaster_id: id.to_string(),
+                broadcaster_name: id.to_string(),
+            })
+            .collect()
+    }
+
+    fn ids(channels: &[FavouriteChannel]) -> Vec<&str> {
+        channels.iter().map(|c| c.broadcaster_id.as_str()).collect()
+    }
+
+    #[test]
+    fn saving_keeps_favourites_changed_from_the_tray() {
+        // Opened with a and b, removed a here, while the tray removed b and added c
+        let merged = GtkSettingsWindow::merge_favourites(
+            &channels(&["a", "c"]),
+            &channels(&["a", "b"]),
+            &channels(&["b", "d"]),
+        );
+        assert_eq!(ids(&merged), ["c", "d"]);
+    }
 }
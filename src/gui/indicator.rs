@@ -1,91 +1,234 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, anyhow};
+use chrono::{Local, TimeDelta};
+use futures_util::future::join_all;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::RwLock;
-use tokio::time::{interval, sleep};
+use tokio::sync::{Notify, RwLock, mpsc, watch};
+use tokio::time::{Instant, Interval, interval, interval_at, sleep, timeout};
 use tracing::{debug, error, info, warn};
+use uuid::Uuid;
 
-use crate::api::{Stream, TwitchClient, User};
-use crate::config::Config;
-use crate::gui::notifications::NotificationManager;
+use crate::api::backoff::Backoff;
+use crate::api::error::is_transient;
+use crate::api::image_cache::ImageCache;
+use crate::api::{Stream, TwitchApiError, TwitchClient};
+use crate::config::{
+    AccountConfig, AppDirs, Autostart, Config, ConfigWatcher, CredentialStore, FavouriteChannel,
+    TrayBadge,
+};
+use crate::dbus::DbusService;
+use crate::gui::accounts::{Account, merge_followed_streams};
+use crate::gui::notifications::{NotificationAction, NotificationManager};
 use crate::gui::settings::SettingsWindow;
-use crate::gui::tray::SystemTray;
+use crate::gui::tray::{
+    EndedStream, RejectedAccount, SimpleTray, SystemTray, Tray, TrayState, TrayStatus,
+};
+
+const CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+const AVATAR_SIZE: u32 = 70;
+const USERS_PER_REQUEST: usize = 100;
+// Further streams are summed up so the tooltip stays readable
+const TOOLTIP_UPTIME_LINES: usize = 5;
+// Covers both the browser redirect and the device code flow
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(10 * 60);
 
 pub struct TwitchIndicator {
     config: Arc<RwLock<Config>>,
-    twitch_client: TwitchClient,
+    dirs: AppDirs,
+    credentials: Arc<CredentialStore>,
+    accounts: Vec<Account>,
     notification_manager: NotificationManager,
     current_streams: Vec<Stream>,
-    authenticated_user: Option<User>,
+    // Set after the first successful poll, until then every stream is already live
+    streams_seeded: bool,
+    recently_ended: Vec<EndedStream>,
+    tray_state: watch::Sender<TrayState>,
+    refresh_requested: Arc<Notify>,
+    notification_actions: Option<mpsc::UnboundedReceiver<NotificationAction>>,
+    favourite_tx: mpsc::UnboundedSender<FavouriteChannel>,
+    favourite_rx: Option<mpsc::UnboundedReceiver<FavouriteChannel>>,
+    // Sign-ins requested from the tray run in their own task and report back by account id
+    sign_in_tx: mpsc::UnboundedSender<String>,
+    sign_in_rx: Option<mpsc::UnboundedReceiver<String>>,
+    signed_in_tx: mpsc::UnboundedSender<(String, Result<()>)>,
+    signed_in_rx: Option<mpsc::UnboundedReceiver<(String, Result<()>)>>,
+    signing_in: HashSet<String>,
+    dbus_service: Option<DbusService>,
+    image_cache: Option<ImageCache>,
 }
 
 impl TwitchIndicator {
-    pub async fn new(config: Arc<RwLock<Config>>) -> Result<Self> {
-        let config_read = config.read().await;
+    pub async fn new(
+        config: Arc<RwLock<Config>>,
+        credentials: CredentialStore,
+        dirs: AppDirs,
+    ) -> Result<Self> {
+        let credentials = Arc::new(credentials);
+        let account_configs = config.read().await.accounts();
+        let mut accounts = Vec::new();
+        for account in account_configs {
+            accounts.push(Account::open(account, config.clone(), credentials.clone()).await?);
+        }
 
-        let mut twitch_client =
-            TwitchClient::new(config_read.twitch.client_id.clone(), config.clone());
+        let config_read = config.read().await;
+        let mut notification_manager = NotificationManager::new(config_read.notifications.clone());
 
         drop(config_read);
-        twitch_client.load_token_from_config().await?;
 
-        let config_read = config.read().await;
-        let notification_manager = NotificationManager::new(config_read.notifications.clone());
+        let (action_tx, action_rx) = mpsc::unbounded_channel();
+        notification_manager.set_action_sender(action_tx);
 
-        drop(config_read);
+        let image_cache = match ImageCache::open_default(&dirs).await {
+            Ok(cache) => Some(cache),
+            Err(e) => {
+                warn!(
+                    "Image cache unavailable, notifications will use the generic icon: {:#}",
+                    e
+                );
+                None
+            }
+        };
+
+        let (tray_state, _) = watch::channel(TrayState::default());
+        let (favourite_tx, favourite_rx) = mpsc::unbounded_channel();
+        let (sign_in_tx, sign_in_rx) = mpsc::unbounded_channel();
+        let (signed_in_tx, signed_in_rx) = mpsc::unbounded_channel();
 
         Ok(Self {
             config,
-            twitch_client,
+            dirs,
+            credentials,
+            accounts,
             notification_manager,
             current_streams: Vec::new(),
-            authenticated_user: None,
+            streams_seeded: false,
+            recently_ended: Vec::new(),
+            tray_state,
+            refresh_requested: Arc::new(Notify::new()),
+            notification_actions: Some(action_rx),
+            favourite_tx,
+            favourite_rx: Some(favourite_rx),
+            sign_in_tx,
+            sign_in_rx: Some(sign_in_rx),
+            signed_in_tx,
+            signed_in_rx: Some(signed_in_rx),
+            signing_in: HashSet::new(),
+            dbus_service: None,
+            image_cache,
         })
     }
 
-    pub async fn run(mut self) -> Result<()> {
+    pub async fn run(mut self, headless: bool) -> Result<()> {
         info!("Starting Twitch Indicator application");
 
-        if !self.is_authenticated().await {
-            info!("User not authenticated, starting authentication flow");
-            self.authenticate().await?;
-        } else {
-            info!("User already authenticated, validating token");
-            if let Err(e) = self.validate_and_refresh_token().await {
-                warn!("Token validation failed: {}, re-authenticating", e);
-                self.authenticate().await?;
+        self.reconcile_autostart().await;
+
+        // One account that can't sign in shouldn't keep the others from starting
+        let mut last_error = None;
+        for account in &mut self.accounts {
+            if let Err(e) = account.sign_in().await {
+                error!("Could not sign in {}: {:#}", account.name(), e);
+                account.record_error(&e);
+                last_error = Some(e);
             }
         }
+        if let Some(e) = last_error
+            && self.accounts.iter().all(|a| a.error.is_some())
+        {
+            return Err(e);
+        }
 
-        self.authenticated_user = Some(
-            self.twitch_client
-                .get_user()
-                .await
-                .context("Failed to get authenticated user info")?,
-        );
+        let tray = if headless {
+            Tray::Simple(SimpleTray::new(self.config.clone())?)
+        } else {
+            Tray::System(
+                SystemTray::new(self.config.clone(), &self.dirs)
+                    .context("Failed to create system tray")?,
+            )
+        };
+
+        self.run_with_tray(tray).await
+    }
 
-        if let Some(ref user) = self.authenticated_user {
-            info!("Authenticated as: {} ({})", user.display_name, user.login);
+    // Users already fetched are kept, so a retry only asks for the ones still missing.
+    // Fails only while no account is usable; the others are retried on every update.
+    pub(crate) async fn load_users(&mut self) -> Result<()> {
+        let mut first_error = None;
+        for account in self.accounts.iter_mut().filter(|a| a.user.is_none()) {
+            if let Err(e) = account.load_user().await {
+                first_error.get_or_insert(e);
+            }
+        }
+
+        match first_error {
+            Some(e) if self.accounts.iter().all(|a| a.user.is_none()) => Err(e),
+            Some(e) => {
+                warn!("{:#}, continuing with the other accounts", e);
+                Ok(())
+            }
+            None => Ok(()),
         }
+    }
 
-        let tray = SystemTray::new(self.config.clone()).context("Failed to create system tray")?;
+    // Autostart often runs before the network is up, so keep retrying instead of exiting
+    pub(crate) async fn connect(&mut self) {
+        let mut backoff = Backoff::default();
 
-        self.run_with_tray(tray).await
+        while let Err(e) = self.load_users().await {
+            let delay = backoff.next_delay();
+            if is_transient(&e) {
+                warn!(
+                    "Twitch unreachable (attempt {}), retrying in {}s: {:#}",
+                    backoff.attempts(),
+                    delay.as_secs(),
+                    e
+                );
+                self.publish_tray_state(TrayStatus::Offline { retry_in: delay })
+                    .await;
+            } else {
+                error!("{:#}, retrying in {}s", e, delay.as_secs());
+                self.publish_tray_state(TrayStatus::Error(e.to_string()))
+                    .await;
+                self.recover_from(&e).await;
+            }
+
+            self.wait_for_retry(delay).await;
+        }
     }
 
-    async fn run_with_tray(mut self, mut tray: SystemTray) -> Result<()> {
-        if let Err(e) = self.update_streams().await {
-            error!("Initial stream update failed: {}", e);
+    async fn wait_for_retry(&self, delay: Duration) {
+        tokio::select! {
+            _ = sleep(delay) => {}
+            _ = self.refresh_requested.notified() => {
+                debug!("Manual refresh requested, retrying now");
+            }
         }
+    }
 
-        tray.update_streams(self.current_streams.clone())?;
+    async fn run_with_tray(mut self, tray: Tray) -> Result<()> {
+        let state_rx = self.tray_state.subscribe();
 
-        let tooltip = self.create_tooltip();
-        tray.set_tooltip(&tooltip)?;
+        match DbusService::start(self.config.clone(), self.refresh_requested.clone()).await {
+            Ok(service) => self.dbus_service = Some(service),
+            Err(e) => warn!("D-Bus service unavailable: {:#}", e),
+        }
 
         let config_for_menu = self.config.clone();
+        let dirs_for_menu = self.dirs.clone();
+        let refresh_requested = self.refresh_requested.clone();
+        let favourite_tx = self.favourite_tx.clone();
+        let sign_in_tx = self.sign_in_tx.clone();
 
         let update_handle = tokio::spawn(async move {
+            self.connect().await;
+
+            if let Err(e) = self.update_streams().await {
+                error!("Initial stream update failed: {}", e);
+                self.publish_tray_state(TrayStatus::Error(e.to_string()))
+                    .await;
+            }
+
             self.periodic_update_loop().await;
         });
 
@@ -94,11 +237,13 @@ impl TwitchIndicator {
                 info!("Settings requested - opening GTK configuration");
 
                 let config = config_for_menu.clone();
+                let dirs = dirs_for_menu.clone();
                 std::thread::spawn(|| {
                     let rt = tokio::runtime::Runtime::new().unwrap();
+                    #[allow(clippy::collapsible_if)]
                     rt.block_on(async {
                         if let Ok(mut gtk_settings) =
-                            crate::gui::gtk_settings::GtkSettingsWindow::new(config).await
+                            crate::gui::gtk_settings::GtkSettingsWindow::new(config, dirs).await
                         {
                             if let Err(e) = gtk_settings.show_sync() {
                                 eprintln!("Failed to show GTK settings: {e}");
@@ -109,13 +254,29 @@ impl TwitchIndicator {
             }
             "refresh" => {
                 info!("Manual refresh requested");
+                refresh_requested.notify_one();
             }
             _ => {
-                debug!("Unknown menu action: {}", action);
+                if let Some(account_id) = action.strip_prefix("sign-in:") {
+                    let _ = sign_in_tx.send(account_id.to_string());
+                    return;
+                }
+                match action
+                    .strip_prefix("favourite:")
+                    .and_then(|channel| channel.split_once(':'))
+                {
+                    Some((broadcaster_id, broadcaster_name)) => {
+                        let _ = favourite_tx.send(FavouriteChannel {
+                            broadcaster_id: broadcaster_id.to_string(),
+                            broadcaster_name: broadcaster_name.to_string(),
+                        });
+                    }
+                    None => debug!("Unknown menu action: {}", action),
+                }
             }
         };
 
-        let tray_result = tray.run(menu_handler).await;
+        let tray_result = tray.run(state_rx, menu_handler).await;
 
         update_handle.abort();
 
@@ -123,59 +284,229 @@ impl TwitchIndicator {
     }
 
     async fn periodic_update_loop(&mut self) {
-        let config_read = self.config.read().await;
-        let refresh_interval =
-            Duration::from_secs(config_read.twitch.refresh_interval_minutes * 60);
-        drop(config_read);
+        let refresh_interval = self.config.read().await.refresh_interval();
 
         let mut interval_timer = interval(refresh_interval);
+        let mut backoff = Backoff::default();
+        let mut config_check = interval(CONFIG_CHECK_INTERVAL);
+        let mut config_watcher = ConfigWatcher::for_default_config(&self.dirs).await;
+        let refresh_requested = self.refresh_requested.clone();
+        let mut notification_actions = self
+            .notification_actions
+            .take()
+            .unwrap_or_else(|| mpsc::unbounded_channel().1);
+        let mut favourite_rx = self
+            .favourite_rx
+            .take()
+            .unwrap_or_else(|| mpsc::unbounded_channel().1);
+        let mut sign_in_rx = self
+            .sign_in_rx
+            .take()
+            .unwrap_or_else(|| mpsc::unbounded_channel().1);
+        let mut signed_in_rx = self
+            .signed_in_rx
+            .take()
+            .unwrap_or_else(|| mpsc::unbounded_channel().1);
 
         loop {
-            interval_timer.tick().await;
-
-            if let Err(e) = self.update_streams().await {
-                error!("Failed to update streams: {}", e);
-
-                let error_msg = e.to_string();
-                if error_msg.contains("Authentication failed")
-                    || error_msg.contains("token may be expired")
-                    || error_msg.contains("Unauthorized")
-                {
-                    warn!("Authentication error detected, attempting re-authentication");
-                    if let Err(auth_err) = self.authenticate().await {
-                        error!("Re-authentication failed: {}", auth_err);
-                    } else if let Ok(user_info) = self.twitch_client.get_user().await {
-                        self.authenticated_user = Some(user_info);
-                        info!("Re-authentication completed successfully");
+            let manual_refresh = tokio::select! {
+                _ = interval_timer.tick() => false,
+                _ = refresh_requested.notified() => true,
+                Some(action) = notification_actions.recv() => {
+                    self.handle_notification_action(action).await;
+                    continue;
+                }
+                Some(channel) = favourite_rx.recv() => {
+                    self.toggle_favourite(channel).await;
+                    continue;
+                }
+                Some(account_id) = sign_in_rx.recv() => {
+                    self.start_sign_in(account_id);
+                    continue;
+                }
+                Some((account_id, result)) = signed_in_rx.recv() => {
+                    self.finish_sign_in(&account_id, result).await;
+                    continue;
+                }
+                _ = config_check.tick() => {
+                    match config_watcher.poll().await {
+                        Ok(Some(new_config)) => {
+                            self.apply_config(new_config, &mut interval_timer).await;
+                        }
+                        Ok(None) => {}
+                        Err(e) => warn!("Ignoring invalid config change: {:#}", e),
                     }
+                    continue;
                 }
+            };
+
+            if manual_refresh {
+                debug!("Running manual refresh, resetting poll interval");
+                interval_timer.reset();
+                self.publish_tray_state(TrayStatus::Refreshing).await;
+            }
+
+            if let Some(delay) = self.poll_streams(&mut backoff).await {
+                interval_timer.reset_after(delay);
             }
 
             sleep(Duration::from_secs(1)).await;
         }
     }
 
-    async fn update_streams(&mut self) -> Result<()> {
+    // Returns how long to wait before retrying when Twitch could not be reached
+    pub(crate) async fn poll_streams(&mut self, backoff: &mut Backoff) -> Option<Duration> {
+        match self.update_streams().await {
+            Ok(()) => {
+                backoff.reset();
+                None
+            }
+            Err(e) if is_transient(&e) => {
+                let delay = backoff.next_delay();
+                warn!(
+                    "Twitch unreachable, retrying in {}s: {:#}",
+                    delay.as_secs(),
+                    e
+                );
+                self.publish_tray_state(TrayStatus::Offline { retry_in: delay })
+                    .await;
+                Some(delay)
+            }
+            Err(e) => {
+                error!("Failed to update streams: {:#}", e);
+                self.publish_tray_state(TrayStatus::Error(e.to_string()))
+                    .await;
+                self.recover_from(&e).await;
+                None
+            }
+        }
+    }
+
+    async fn recover_from(&mut self, err: &anyhow::Error) {
+        match TwitchApiError::find(err) {
+            Some(TwitchApiError::Unauthorized) => {
+                warn!("Twitch rejected the stored tokens, waiting for a new sign-in");
+                self.publish_tray_state(self.settled_status()).await
+            }
+            Some(TwitchApiError::RateLimited { reset }) => match reset {
+                Some(reset) => warn!("Rate limited by Twitch until {}", reset),
+                None => warn!("Rate limited by Twitch"),
+            },
+            Some(TwitchApiError::Network(e)) => {
+                warn!("Network error, will retry on next update: {}", e)
+            }
+            Some(TwitchApiError::Server { status, .. }) => {
+                warn!("Twitch API returned {}, will retry on next update", status)
+            }
+            Some(TwitchApiError::Decode(e)) => warn!("Unexpected Twitch API response: {}", e),
+            None => {}
+        }
+    }
+
+    // Signing in waits on the browser, so it runs beside the updates of the other accounts
+    pub(crate) fn start_sign_in(&mut self, account_id: String) {
+        let Some(account) = self.accounts.iter().find(|a| a.config.id == account_id) else {
+            debug!("Sign-in requested for unknown account {}", account_id);
+            return;
+        };
+        if !self.signing_in.insert(account_id.clone()) {
+            debug!("Sign-in of {} is already in progress", account.name());
+            return;
+        }
+        info!("Signing in {} again", account.name());
+
+        let config = self.config.clone();
+        let credentials = self.credentials.clone();
+        let signed_in_tx = self.signed_in_tx.clone();
+        tokio::spawn(async move {
+            let twitch_config = config.read().await.twitch.clone();
+            let mut client = TwitchClient::new(&twitch_config, config, credentials, &account_id);
+            let result = match timeout(SIGN_IN_TIMEOUT, client.authenticate()).await {
+                Ok(result) => result,
+                Err(_) => Err(anyhow!(
+                    "Sign-in was not completed within {} minutes",
+                    SIGN_IN_TIMEOUT.as_secs() / 60
+                )),
+            };
+            let _ = signed_in_tx.send((account_id, result));
+        });
+    }
+
+    // A failed sign-in leaves the account waiting for the next request from the tray
+    pub(crate) async fn finish_sign_in(&mut self, account_id: &str, result: Result<()>) {
+        self.signing_in.remove(account_id);
+        let Some(account) = self.accounts.iter_mut().find(|a| a.config.id == account_id) else {
+            return;
+        };
+
+        match result {
+            // The next update picks up the stored tokens
+            Ok(()) => {
+                info!("Signed in {} again", account.name());
+                self.refresh_requested.notify_one();
+            }
+            Err(e) => error!("Sign-in of {} failed: {:#}", account.name(), e),
+        }
+    }
+
+    // Status once an update went through, which still asks for accounts needing a sign-in
+    fn settled_status(&self) -> TrayStatus {
+        let rejected: Vec<RejectedAccount> = self
+            .accounts
+            .iter()
+            .filter(|a| a.rejected)
+            .map(|a| RejectedAccount {
+                account_id: a.config.id.clone(),
+                name: a.name().to_string(),
+            })
+            .collect();
+
+        if rejected.is_empty() {
+            TrayStatus::Ok
+        } else {
+            TrayStatus::NeedsSignIn(rejected)
+        }
+    }
+
+    pub(crate) async fn update_streams(&mut self) -> Result<()> {
         debug!("Updating streams");
 
-        let user = self
-            .authenticated_user
-            .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("No authenticated user"))?;
+        // Accounts added while running fetch their user on the first update
+        let mut failures = Vec::new();
+        for account in &mut self.accounts {
+            if let Err(e) = account.update_streams().await {
+                failures.push(e);
+            }
+        }
 
-        let new_streams = self
-            .twitch_client
-            .get_followed_streams(&user.id)
-            .await
-            .context("Failed to get followed streams")?;
+        if failures.len() == self.accounts.len() && !failures.is_empty() {
+            return Err(failures.swap_remove(0));
+        }
+        for e in &failures {
+            warn!("{:#}, showing streams of the other accounts", e);
+        }
+
+        let (new_streams, notified) = merge_followed_streams(&self.accounts);
 
         debug!("Retrieved {} live streams", new_streams.len());
 
-        self.notification_manager.notify_new_streams(&new_streams)?;
+        self.load_avatars(&notified).await;
+        self.notification_manager.notify_new_streams(&notified)?;
+        self.notification_manager.notify_stream_changes(&notified)?;
 
         self.notification_manager.update_live_streams(&new_streams);
 
+        if let Some(ref dbus_service) = self.dbus_service {
+            let went_live = self.went_live(&new_streams);
+            if let Err(e) = dbus_service.publish(&new_streams, &went_live).await {
+                warn!("Failed to publish streams over D-Bus: {:#}", e);
+            }
+        }
+
+        self.track_ended_streams(&new_streams).await;
         self.current_streams = new_streams;
+        self.streams_seeded = true;
+        self.publish_tray_state(self.settled_status()).await;
 
         info!(
             "Stream update completed: {} live streams",
@@ -184,43 +515,357 @@ impl TwitchIndicator {
         Ok(())
     }
 
-    async fn is_authenticated(&self) -> bool {
+    // Streams already live at startup are not announced
+    fn went_live(&self, new_streams: &[Stream]) -> Vec<Stream> {
+        if !self.streams_seeded {
+            return Vec::new();
+        }
+
+        new_streams
+            .iter()
+            .filter(|stream| !self.current_streams.iter().any(|s| s.id == stream.id))
+            .cloned()
+            .collect()
+    }
+
+    // Only streams not seen yet, or whose avatar was evicted, are looked up, so
+    // avatars are not refetched every poll
+    async fn load_avatars(&mut self, streams: &[Stream]) {
+        // Any account whose token currently works can look up streamers
+        let usable = self
+            .accounts
+            .iter()
+            .find(|a| a.user.is_some() && a.error.is_none());
+        let (Some(image_cache), Some(account)) = (&self.image_cache, usable) else {
+            return;
+        };
+
+        let mut missing: Vec<String> = streams
+            .iter()
+            .filter(|s| {
+                !self.notification_manager.is_tracked(&s.id)
+                    || self.notification_manager.avatar_is_stale(&s.user_id)
+            })
+            .filter(|s| !self.notification_manager.has_avatar(&s.user_id))
+            .map(|s| s.user_id.clone())
+            .collect();
+        missing.sort();
+        missing.dedup();
+
+        for user_ids in missing.chunks(USERS_PER_REQUEST) {
+            let users = match account.client.get_users_by_ids(user_ids).await {
+                Ok(users) => users,
+                Err(e) => {
+                    warn!("Failed to look up streamer avatars: {:#}", e);
+                    return;
+                }
+            };
+
+            // Downloads run together, bounded by the image cache's download limit
+            let downloads = users
+                .iter()
+                .map(|user| image_cache.profile_image(&account.client, user, AVATAR_SIZE));
+            let results = join_all(downloads).await;
+
+            for (user, result) in users.iter().zip(results) {
+                match result {
+                    Ok(path) => self.notification_manager.set_avatar(&user.id, path),
+                    Err(e) => debug!("No avatar for {}: {:#}", user.login, e),
+                }
+            }
+        }
+    }
+
+    async fn track_ended_streams(&mut self, new_streams: &[Stream]) {
+        let now = Local::now();
+        let is_live = |user_id: &str| new_streams.iter().any(|s| s.user_id == user_id);
+
+        for stream in &self.current_streams {
+            if !is_live(&stream.user_id) {
+                self.recently_ended.retain(|e| e.user_id != stream.user_id);
+                self.recently_ended.insert(0, EndedStream::new(stream, now));
+            }
+        }
+
+        let hours = self.config.read().await.ui.recently_ended_hours;
+        let window = TimeDelta::hours(hours.min(24 * 365) as i64);
+        self.recently_ended
+            .retain(|e| !is_live(&e.user_id) && now.signed_duration_since(e.ended_at) < window);
+    }
+
+    async fn publish_tray_state(&self, status: TrayStatus) {
         let config = self.config.read().await;
-        config.is_authenticated()
+        self.tray_state.send_replace(TrayState {
+            streams: self.current_streams.clone(),
+            tooltip: self.create_tooltip(config.ui.show_uptime),
+            status,
+            favourites: config.ui.favourite_channels.clone(),
+            pin_favourites: config.ui.show_selected_channels_on_top,
+            recently_ended: self.recently_ended.clone(),
+            show_uptime: config.ui.show_uptime,
+            badge_count: match config.ui.tray_badge {
+                TrayBadge::Live => Some(self.current_streams.len()),
+                TrayBadge::Favourites => Some(
+                    self.current_streams
+                        .iter()
+                        .filter(|s| config.is_favourite(&s.user_id))
+                        .count(),
+                ),
+                TrayBadge::Off => None,
+            },
+            menu_layout: config.ui.menu_layout,
+        });
+    }
+
+    async fn toggle_favourite(&mut self, channel: FavouriteChannel) {
+        let mut config = self.config.write().await;
+        let name = channel.broadcaster_name.clone();
+
+        if config.toggle_favourite(channel) {
+            info!("Added {} to favourites", name);
+        } else {
+            info!("Removed {} from favourites", name);
+        }
+
+        if let Err(e) = config.save_default(&self.dirs).await {
+            error!("Failed to save favourites: {}", e);
+        }
+        drop(config);
+
+        let status = self.tray_state.borrow().status.clone();
+        self.publish_tray_state(status).await;
+    }
+
+    async fn apply_config(&mut self, new_config: Config, interval_timer: &mut Interval) {
+        let mut config = self.config.write().await;
+        if *config == new_config {
+            return;
+        }
+
+        info!("Configuration changed on disk, applying");
+
+        let new_interval = new_config.refresh_interval();
+        if new_interval != config.refresh_interval() {
+            info!(
+                "Refresh interval changed to {} minutes",
+                new_config.twitch.refresh_interval_minutes
+            );
+            *interval_timer = interval_at(Instant::now() + new_interval, new_interval);
+        }
+
+        self.notification_manager
+            .update_config(new_config.notifications.clone());
+
+        let autostart_changed = new_config.general.autostart != config.general.autostart;
+        let accounts_changed = new_config.accounts() != config.accounts();
+
+        *config = new_config;
+        drop(config);
+
+        if autostart_changed {
+            self.reconcile_autostart().await;
+        }
+
+        if accounts_changed {
+            self.sync_accounts().await;
+        }
+
+        let status = self.tray_state.borrow().status.clone();
+        self.publish_tray_state(status).await;
+    }
+
+    // Accounts still listed keep their session, new ones are signed in on the next update
+    async fn sync_accounts(&mut self) {
+        let mut accounts = Vec::new();
+        for config in self.config.read().await.accounts() {
+            match self.accounts.iter().position(|a| a.config.id == config.id) {
+                Some(index) => {
+                    let mut account = self.accounts.swap_remove(index);
+                    account.config = config;
+                    accounts.push(account);
+                }
+                None => {
+                    match Account::open(config, self.config.clone(), self.credentials.clone()).await
+                    {
+                        Ok(account) => accounts.push(account),
+                        Err(e) => error!("Failed to open account: {:#}", e),
+                    }
+                }
+            }
+        }
+
+        info!("Now following streams for {} accounts", accounts.len());
+        self.accounts = accounts;
+        self.refresh_requested.notify_one();
+    }
+
+    pub async fn add_account(&mut self) -> Result<String> {
+        self.identify_accounts().await;
+
+        let config = AccountConfig::new(&Uuid::new_v4().to_string());
+        let mut account =
+            Account::open(config, self.config.clone(), self.credentials.clone()).await?;
+        account.authenticate().await?;
+        let user = account.load_user().await?.clone();
+
+        // Signing in to a listed account again replaces its tokens, which is how an
+        // account Twitch rejected is signed in without the tray
+        if let Some(existing) = self.accounts.iter().find(|a| a.matches(&user.login)) {
+            let tokens = self.credentials.load(&account.config.id).await?;
+            self.credentials.save(&existing.config.id, &tokens).await?;
+            self.credentials.clear(&account.config.id).await?;
+            info!("Renewed the tokens of {}", user.login);
+            return Ok(user.display_name);
+        }
+        account.config.login = user.login.clone();
+
+        // The implicit default account is only kept if it was ever signed in
+        let implicit_default = self.config.read().await.twitch.accounts.is_empty();
+        let mut accounts = Vec::new();
+        for existing in &self.accounts {
+            if implicit_default && !existing.client.has_access_token().await {
+                continue;
+            }
+            accounts.push(Self::recorded_config(existing));
+        }
+        accounts.push(account.config.clone());
+
+        let mut config = self.config.write().await;
+        config.twitch.accounts = accounts;
+        config.save_default(&self.dirs).await?;
+        drop(config);
+
+        self.accounts.push(account);
+        Ok(user.display_name)
     }
 
-    async fn authenticate(&mut self) -> Result<()> {
-        info!("Starting Twitch authentication");
+    pub async fn remove_account(&mut self, name: &str) -> Result<()> {
+        self.identify_accounts().await;
+
+        let index = self
+            .accounts
+            .iter()
+            .position(|a| a.matches(name))
+            .ok_or_else(|| anyhow!("No account named {name}"))?;
+        let removed = self.accounts.remove(index);
 
-        self.twitch_client
-            .authenticate()
+        self.credentials
+            .clear(&removed.config.id)
             .await
-            .context("Authentication failed")?;
+            .with_context(|| format!("Failed to clear tokens from {}", self.credentials.name()))?;
 
-        info!("Authentication completed successfully");
+        let mut config = self.config.write().await;
+        config.twitch.accounts = self.accounts.iter().map(Self::recorded_config).collect();
+        config.save_default(&self.dirs).await?;
+
+        info!("Removed account {}", removed.name());
         Ok(())
     }
 
-    async fn validate_and_refresh_token(&mut self) -> Result<()> {
-        match self.twitch_client.validate_token().await {
-            Ok(validation) => {
-                debug!("Token valid for user: {}", validation.login);
-                Ok(())
+    // Accounts from before logins were recorded can only be matched by fetching their user
+    async fn identify_accounts(&mut self) {
+        for account in &mut self.accounts {
+            if account.config.login.is_empty()
+                && account.client.has_access_token().await
+                && let Err(e) = account.load_user().await
+            {
+                debug!("Could not identify account {}: {:#}", account.config.id, e);
             }
-            Err(e) => {
-                info!("Token validation failed, re-authentication required");
-                Err(e)
+        }
+    }
+
+    fn recorded_config(account: &Account) -> AccountConfig {
+        let mut config = account.config.clone();
+        if let Some(user) = &account.user {
+            config.login = user.login.clone();
+        }
+        config
+    }
+
+    async fn reconcile_autostart(&self) {
+        let enabled = self.config.read().await.general.autostart;
+        let result = match Autostart::for_current_exe(&self.dirs) {
+            Ok(autostart) => autostart.reconcile(enabled).await,
+            Err(e) => Err(e),
+        };
+
+        if let Err(e) = result {
+            error!("Failed to update autostart entry: {:#}", e);
+        }
+    }
+
+    async fn handle_notification_action(&mut self, action: NotificationAction) {
+        debug!("Handling notification action: {:?}", action);
+
+        match action {
+            NotificationAction::OpenStream { url } => {
+                let config = self.config.read().await;
+                if let Err(e) = config.open_stream_url(&url) {
+                    error!("Failed to open stream: {}", e);
+                }
+            }
+            NotificationAction::OpenChat { login } => {
+                let config = self.config.read().await;
+                if let Err(e) = config.open_chat(&login) {
+                    error!("Failed to open chat: {}", e);
+                }
+            }
+            NotificationAction::Mute { user_id, user_name } => {
+                let mut config = self.config.write().await;
+                if !config.mute_channel(&user_id) {
+                    return;
+                }
+
+                self.notification_manager
+                    .update_config(config.notifications.clone());
+
+                if let Err(e) = config.save_default(&self.dirs).await {
+                    error!("Failed to save muted channel: {}", e);
+                } else {
+                    info!("Muted notifications for {}", user_name);
+                }
             }
         }
     }
 
-    fn create_tooltip(&self) -> String {
-        if let Some(ref user) = self.authenticated_user {
-            format!(
+    fn create_tooltip(&self, show_uptime: bool) -> String {
+        let users: Vec<&str> = self
+            .accounts
+            .iter()
+            .filter_map(|a| a.user.as_ref())
+            .map(|user| user.display_name.as_str())
+            .collect();
+
+        if !users.is_empty() {
+            let mut tooltip = format!(
                 "Twitch Indicator - {} ({} live streams)",
-                user.display_name,
+                users.join(", "),
                 self.current_streams.len()
-            )
+            );
+
+            for account in &self.accounts {
+                if let Some(error) = &account.error {
+                    tooltip.push_str(&format!("\n{} unavailable: {}", account.name(), error));
+                }
+            }
+
+            if show_uptime {
+                let mut streams: Vec<&Stream> = self.current_streams.iter().collect();
+                streams.sort_by_key(|s| std::cmp::Reverse(s.viewer_count));
+
+                for stream in streams.iter().take(TOOLTIP_UPTIME_LINES) {
+                    tooltip.push_str(&format!(
+                        "\n{}: {}",
+                        stream.user_name,
+                        stream.formatted_uptime()
+                    ));
+                }
+                if streams.len() > TOOLTIP_UPTIME_LINES {
+                    tooltip.push_str(&format!("\n+{} more", streams.len() - TOOLTIP_UPTIME_LINES));
+                }
+            }
+
+            tooltip
         } else {
             "Twitch Indicator - Not authenticated".to_string()
         }
@@ -241,6 +886,47 @@ impl TwitchIndicator {
         Ok(())
     }
 
+    pub async fn diagnostics(&self) -> Result<String> {
+        let mut report = vec![
+            format!("Twitch Indicator v{}", env!("CARGO_PKG_VERSION")),
+            format!("Config directory: {}", self.dirs.config.display()),
+            format!("Credentials: {}", self.credentials.name()),
+        ];
+
+        for account in &self.accounts {
+            report.push(format!("Account: {}", account.config.id));
+            let client = &account.client;
+            if let Some(error) = &account.error {
+                report.push(format!("Last error: {error}"));
+            }
+
+            if !client.has_access_token().await {
+                report.push("Authentication: not signed in".to_string());
+                continue;
+            }
+
+            match client.validate_token().await {
+                Ok(validation) => report.push(format!(
+                    "Authentication: token valid for {} (expires in {}s)",
+                    validation.login, validation.expires_in
+                )),
+                Err(e) => report.push(format!("Authentication: {e:#}")),
+            }
+
+            match client.get_user().await {
+                Ok(user) => report.push(format!("User: {} ({})", user.display_name, user.id)),
+                Err(e) => report.push(format!("User: {e:#}")),
+            }
+
+            match client.rate_limit().await {
+                Some(budget) => report.push(format!("Rate limit: {budget}")),
+                None => report.push("Rate limit: unknown".to_string()),
+            }
+        }
+
+        Ok(report.join("\n"))
+    }
+
     pub async fn import_settings(&self, path: &str) -> Result<()> {
         info!("Importing settings from: {}", path);
 
@@ -252,7 +938,8 @@ impl TwitchIndicator {
             toml::from_str(&content).context("Failed to parse imported settings")?;
 
         let settings_window =
-            SettingsWindow::new(Arc::new(RwLock::new(new_config.clone()))).await?;
+            SettingsWindow::new(Arc::new(RwLock::new(new_config.clone())), self.dirs.clone())
+                .await?;
         settings_window
             .validate()
             .context("Imported settings are invalid")?;
@@ -260,8 +947,7 @@ impl TwitchIndicator {
         let mut config_write = self.config.write().await;
         *config_write = new_config;
 
-        let config_path = Config::get_config_dir()?.join("config.toml");
-        config_write.save(&config_path).await?;
+        config_write.save_default(&self.dirs).await?;
 
         info!("Settings imported and applied successfully");
         Ok(())
@@ -271,9 +957,542 @@ impl TwitchIndicator {
     pub fn get_current_streams(&self) -> &[Stream] {
         &self.current_streams
     }
+}
 
-    #[allow(dead_code)]
-    pub fn get_authenticated_user(&self) -> Option<&User> {
-        self.authenticated_user.as_ref()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{AuthMode, DEFAULT_ACCOUNT, Tokens};
+    use crate::testing::fake_twitch::{ACCESS_TOKEN, CLIENT_ID, stream, user};
+    use crate::testing::{FakeTwitch, RecordingNotifier};
+    use notify_rust::Hint;
+
+    async fn new_indicator(
+        fake: &FakeTwitch,
+        config: Config,
+        credentials: CredentialStore,
+    ) -> TwitchIndicator {
+        let config = Arc::new(RwLock::new(config));
+        let mut indicator = TwitchIndicator::new(config, credentials, fake.dirs())
+            .await
+            .unwrap();
+        record_notifications(&mut indicator);
+        indicator
+    }
+
+    async fn indicator_for(fake: &FakeTwitch) -> TwitchIndicator {
+        let mut indicator = new_indicator(fake, fake.config(), fake.credentials().await).await;
+        indicator.load_users().await.unwrap();
+        indicator
+    }
+
+    // Keeps notifications off the desktop and returns what was shown
+    fn record_notifications(indicator: &mut TwitchIndicator) -> RecordingNotifier {
+        let notifier = RecordingNotifier::new();
+        indicator
+            .notification_manager
+            .set_notifier(Box::new(notifier.clone()));
+        notifier
+    }
+
+    #[tokio::test]
+    async fn update_streams_notifies_only_new_streams() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 20),
+        ];
+        let mut indicator = indicator_for(&fake).await;
+        let notifications = record_notifications(&mut indicator);
+
+        indicator.update_streams().await.unwrap();
+        assert_eq!(indicator.get_current_streams().len(), 2);
+        assert_eq!(
+            notifications.summaries(),
+            ["alpha is now live!", "beta is now live!"]
+        );
+
+        indicator.update_streams().await.unwrap();
+        assert!(notifications.summaries().is_empty());
+
+        fake.state().streams.push(stream("s3", "u3", "gamma", 5));
+        indicator.update_streams().await.unwrap();
+        assert_eq!(notifications.summaries(), ["gamma is now live!"]);
+    }
+
+    #[tokio::test]
+    async fn restarted_stream_is_notified_again() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![stream("s1", "u1", "alpha", 10)];
+        let mut indicator = indicator_for(&fake).await;
+        let notifications = record_notifications(&mut indicator);
+
+        indicator.update_streams().await.unwrap();
+        assert_eq!(notifications.summaries().len(), 1);
+
+        fake.state().streams.clear();
+        indicator.update_streams().await.unwrap();
+        assert!(indicator.get_current_streams().is_empty());
+
+        fake.state().streams = vec![stream("s9", "u1", "alpha", 10)];
+        indicator.update_streams().await.unwrap();
+        assert_eq!(notifications.summaries(), ["alpha is now live!"]);
+    }
+
+    #[tokio::test]
+    async fn update_streams_publishes_tray_state() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![stream("s1", "u1", "alpha", 10)];
+        let mut indicator = indicator_for(&fake).await;
+        let mut state_rx = indicator.tray_state.subscribe();
+
+        indicator.update_streams().await.unwrap();
+
+        assert!(state_rx.has_changed().unwrap());
+        let state = state_rx.borrow_and_update().clone();
+        assert_eq!(state.streams.len(), 1);
+        assert_eq!(state.status, TrayStatus::Ok);
+        assert!(state.tooltip.contains("1 live streams"));
+        assert_eq!(state.badge_count, Some(1));
+    }
+
+    #[tokio::test]
+    async fn streams_live_at_startup_are_not_reported_as_went_live() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![stream("s1", "u1", "alpha", 10)];
+        let mut indicator = indicator_for(&fake).await;
+        let startup = vec![stream("s1", "u1", "alpha", 10)];
+        assert!(indicator.went_live(&startup).is_empty());
+
+        indicator.update_streams().await.unwrap();
+        let later = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 20),
+        ];
+        let went_live = indicator.went_live(&later);
+        assert_eq!(went_live.len(), 1);
+        assert_eq!(went_live[0].user_login, "beta");
+    }
+
+    #[tokio::test]
+    async fn tooltip_lists_uptime_of_the_most_watched_streams() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = (1..=7)
+            .map(|i| stream(&format!("s{i}"), &format!("u{i}"), &format!("user{i}"), i))
+            .collect();
+        let mut indicator = indicator_for(&fake).await;
+        indicator.update_streams().await.unwrap();
+
+        let tooltip = indicator.create_tooltip(true);
+        let lines: Vec<&str> = tooltip.lines().skip(1).collect();
+        assert_eq!(lines.len(), TOOLTIP_UPTIME_LINES + 1);
+        assert!(lines[0].starts_with("user7: "));
+        assert!(lines[4].starts_with("user3: "));
+        assert_eq!(lines[5], "+2 more");
+
+        assert_eq!(indicator.create_tooltip(false).lines().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn ended_streams_are_listed_until_channel_returns() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 20),
+        ];
+        let mut indicator = indicator_for(&fake).await;
+        let state_rx = indicator.tray_state.subscribe();
+        indicator.update_streams().await.unwrap();
+
+        fake.state().streams.retain(|s| s.user_id == "u2");
+        indicator.update_streams().await.unwrap();
+
+        let ended = state_rx.borrow().recently_ended.clone();
+        assert_eq!(ended.len(), 1);
+        assert_eq!(ended[0].user_name, "alpha");
+        assert!(ended[0].duration.is_some());
+
+        fake.state().streams.push(stream("s3", "u1", "alpha", 10));
+        indicator.update_streams().await.unwrap();
+        assert!(state_rx.borrow().recently_ended.is_empty());
+    }
+
+    #[tokio::test]
+    async fn notifications_use_cached_streamer_avatar() {
+        let fake = FakeTwitch::start();
+        let mut alpha = user("u1", "alpha");
+        alpha.profile_image_url = fake.image_url("alpha-profile_image-300x300.png");
+        {
+            let mut state = fake.state();
+            state.users.push(alpha);
+            state.users.push(user("u2", "beta"));
+            state.images.insert(
+                "alpha-profile_image-70x70.png".to_string(),
+                b"avatar".to_vec(),
+            );
+            state.streams = vec![
+                stream("s1", "u1", "alpha", 10),
+                stream("s2", "u2", "beta", 20),
+            ];
+        }
+        let mut indicator = indicator_for(&fake).await;
+        let notifications = record_notifications(&mut indicator);
+
+        indicator.update_streams().await.unwrap();
+
+        let images: Vec<Option<String>> = notifications
+            .take()
+            .into_iter()
+            .map(|n| {
+                n.hints.iter().find_map(|hint| match hint {
+                    Hint::ImagePath(path) => Some(path.clone()),
+                    _ => None,
+                })
+            })
+            .collect();
+        assert_eq!(images.len(), 2);
+        let avatar = images[0].as_ref().expect("alpha should have an avatar");
+        assert_eq!(std::fs::read(avatar).unwrap(), b"avatar");
+        assert!(images[1].is_none());
+
+        let user_lookups = || {
+            fake.state()
+                .requests
+                .iter()
+                .filter(|r| r.starts_with("/helix/users?"))
+                .count()
+        };
+        let lookups = user_lookups();
+        indicator.update_streams().await.unwrap();
+        assert_eq!(user_lookups(), lookups);
+
+        // An avatar evicted from the image cache is fetched again
+        std::fs::remove_file(avatar).unwrap();
+        indicator.update_streams().await.unwrap();
+        assert_eq!(user_lookups(), lookups + 1);
+        assert!(indicator.notification_manager.has_avatar("u1"));
+    }
+
+    #[tokio::test]
+    async fn toggling_favourite_republishes_tray_state() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![stream("s1", "u1", "alpha", 10)];
+        let mut indicator = indicator_for(&fake).await;
+        indicator.update_streams().await.unwrap();
+        let mut state_rx = indicator.tray_state.subscribe();
+
+        let alpha = FavouriteChannel {
+            broadcaster_id: "u1".to_string(),
+            broadcaster_name: "alpha".to_string(),
+        };
+        indicator.toggle_favourite(alpha.clone()).await;
+
+        let state = state_rx.borrow_and_update().clone();
+        assert_eq!(state.favourites.len(), 1);
+        assert_eq!(state.favourites[0], alpha);
+        assert!(state.pin_favourites);
+        assert_eq!(state.streams.len(), 1);
+
+        let saved = Config::load_from(&fake.dirs().config_file()).await.unwrap();
+        assert_eq!(saved.ui.favourite_channels, std::slice::from_ref(&alpha));
+
+        indicator.toggle_favourite(alpha).await;
+        assert!(state_rx.borrow_and_update().favourites.is_empty());
+    }
+
+    #[tokio::test]
+    async fn unreachable_twitch_shows_offline_until_an_update_succeeds() {
+        let fake = FakeTwitch::start();
+        fake.state().streams = vec![stream("s1", "u1", "alpha", 10)];
+        let mut indicator = indicator_for(&fake).await;
+        let state_rx = indicator.tray_state.subscribe();
+        let mut backoff = Backoff::default();
+
+        fake.state().unavailable_requests = 2;
+        let first = indicator.poll_streams(&mut backoff).await.unwrap();
+        assert_eq!(
+            state_rx.borrow().status,
+            TrayStatus::Offline { retry_in: first }
+        );
+        indicator.poll_streams(&mut backoff).await.unwrap();
+        assert_eq!(backoff.attempts(), 2);
+
+        assert_eq!(indicator.poll_streams(&mut backoff).await, None);
+        assert_eq!(state_rx.borrow().status, TrayStatus::Ok);
+        assert_eq!(state_rx.borrow().streams.len(), 1);
+        assert_eq!(backoff.attempts(), 0);
+
+        fake.state().unavailable_requests = 1;
+        indicator.poll_streams(&mut backoff).await.unwrap();
+        assert_eq!(backoff.attempts(), 1);
+    }
+
+    #[tokio::test]
+    async fn rejected_token_surfaces_as_update_error() {
+        let fake = FakeTwitch::start();
+        let mut indicator = indicator_for(&fake).await;
+        fake.state().access_token = "rotated".to_string();
+
+        let err = indicator.update_streams().await.unwrap_err();
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn diagnostics_report_rate_limit_budget() {
+        let fake = FakeTwitch::start();
+        let indicator = indicator_for(&fake).await;
+
+        let report = indicator.diagnostics().await.unwrap();
+        assert!(report.contains("User: viewer (1000)"));
+        assert!(report.contains("Rate limit: 798/800 points remaining"));
+    }
+
+    #[tokio::test]
+    async fn startup_retries_while_twitch_is_unreachable() {
+        let fake = FakeTwitch::start();
+        fake.state().unavailable_requests = 1;
+        let mut indicator = new_indicator(&fake, fake.config(), fake.credentials().await).await;
+        let state_rx = indicator.tray_state.subscribe();
+
+        // Skips the backoff delay the same way the Refresh menu item does
+        indicator.refresh_requested.notify_one();
+        indicator.connect().await;
+
+        assert_eq!(indicator.accounts[0].user.as_ref().unwrap().login, "viewer");
+        assert!(matches!(
+            state_rx.borrow().status,
+            TrayStatus::Offline { .. }
+        ));
+    }
+
+    async fn two_account_indicator(fake: &FakeTwitch) -> TwitchIndicator {
+        {
+            let mut state = fake.state();
+            state.users.push(user("2000", "partner"));
+            state
+                .sessions
+                .insert("partner-token".to_string(), "2000".to_string());
+            state
+                .follows
+                .insert("1000".to_string(), vec!["u1".to_string(), "u2".to_string()]);
+            state
+                .follows
+                .insert("2000".to_string(), vec!["u2".to_string(), "u3".to_string()]);
+            state.streams = vec![
+                stream("s1", "u1", "alpha", 10),
+                stream("s2", "u2", "beta", 20),
+                stream("s3", "u3", "gamma", 5),
+            ];
+        }
+
+        let mut config = fake.config();
+        // Fails fast against the fake instead of waiting for a browser redirect
+        config.twitch.auth_mode = AuthMode::DeviceCode;
+        let mut partner = AccountConfig::new("partner");
+        partner.notifications = false;
+        config.twitch.accounts = vec![AccountConfig::new(DEFAULT_ACCOUNT), partner];
+
+        let credentials = fake.credentials().await;
+        credentials
+            .save(
+                "partner",
+                &Tokens {
+                    access_token: Some("partner-token".to_string()),
+                    refresh_token: None,
+                },
+            )
+            .await
+            .unwrap();
+
+        let mut indicator = new_indicator(fake, config, credentials).await;
+        indicator.load_users().await.unwrap();
+        indicator
+    }
+
+    #[tokio::test]
+    async fn streams_of_all_accounts_are_merged() {
+        let fake = FakeTwitch::start();
+        let mut indicator = two_account_indicator(&fake).await;
+        let notifications = record_notifications(&mut indicator);
+
+        indicator.update_streams().await.unwrap();
+
+        let followed_by: Vec<(&str, Vec<String>)> = indicator
+            .get_current_streams()
+            .iter()
+            .map(|s| (s.user_login.as_str(), s.followed_by.clone()))
+            .collect();
+        assert_eq!(
+            followed_by,
+            [
+                ("alpha", vec!["viewer".to_string()]),
+                ("beta", vec!["viewer".to_string(), "partner".to_string()]),
+                ("gamma", vec!["partner".to_string()]),
+            ]
+        );
+        assert_eq!(
+            notifications.summaries(),
+            ["alpha is now live!", "beta is now live!"]
+        );
+        assert!(
+            indicator
+                .tray_state
+                .borrow()
+                .tooltip
+                .starts_with("Twitch Indicator - viewer, partner (3 live streams)")
+        );
+    }
+
+    #[tokio::test]
+    async fn rejected_account_does_not_hide_other_accounts() {
+        let fake = FakeTwitch::start();
+        let mut indicator = two_account_indicator(&fake).await;
+        fake.state().sessions.clear();
+
+        indicator.update_streams().await.unwrap();
+
+        let logins: Vec<&str> = indicator
+            .get_current_streams()
+            .iter()
+            .map(|s| s.user_login.as_str())
+            .collect();
+        assert_eq!(logins, ["alpha", "beta"]);
+        assert!(indicator.accounts[0].error.is_none());
+        let error = indicator.accounts[1].error.clone().unwrap();
+        assert!(error.contains("partner"), "{error}");
+        assert!(error.contains("Authentication failed"), "{error}");
+        assert!(
+            indicator
+                .tray_state
+                .borrow()
+                .tooltip
+                .contains("partner unavailable")
+        );
+
+        assert!(indicator.accounts[1].rejected && !indicator.accounts[0].rejected);
+        assert_eq!(
+            indicator.tray_state.borrow().status,
+            TrayStatus::NeedsSignIn(vec![RejectedAccount {
+                account_id: "partner".to_string(),
+                name: "partner".to_string(),
+            }])
+        );
+
+        // Polling leaves the sign-in to the tray
+        fake.state().requests.clear();
+        indicator.poll_streams(&mut Backoff::default()).await;
+        assert!(
+            !fake
+                .state()
+                .requests
+                .iter()
+                .any(|r| r.starts_with("/oauth2"))
+        );
+
+        fake.state().requests.clear();
+        indicator.start_sign_in("partner".to_string());
+        let signed_in_rx = indicator.signed_in_rx.as_mut().unwrap();
+        let (account_id, result) = signed_in_rx.recv().await.unwrap();
+        assert!(result.is_err());
+        indicator.finish_sign_in(&account_id, result).await;
+        assert_eq!(fake.state().requests, ["/oauth2/device"]);
+        assert!(indicator.accounts[1].rejected && indicator.signing_in.is_empty());
+
+        fake.state().access_token = "rotated".to_string();
+        let err = indicator.update_streams().await.unwrap_err();
+        assert!(matches!(
+            TwitchApiError::find(&err),
+            Some(TwitchApiError::Unauthorized)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rejected_account_picks_up_tokens_of_a_new_sign_in() {
+        let fake = FakeTwitch::start();
+        let mut indicator = two_account_indicator(&fake).await;
+        fake.state().sessions.remove("partner-token");
+        indicator.update_streams().await.unwrap();
+        assert!(indicator.accounts[1].rejected);
+
+        fake.state()
+            .sessions
+            .insert("renewed-token".to_string(), "2000".to_string());
+        indicator
+            .credentials
+            .save(
+                "partner",
+                &Tokens {
+                    access_token: Some("renewed-token".to_string()),
+                    refresh_token: None,
+                },
+            )
+            .await
+            .unwrap();
+        indicator.update_streams().await.unwrap();
+
+        assert!(!indicator.accounts[1].rejected);
+        assert_eq!(indicator.tray_state.borrow().status, TrayStatus::Ok);
+    }
+
+    #[tokio::test]
+    async fn streams_of_a_failing_account_are_not_reported_as_ended() {
+        let fake = FakeTwitch::start();
+        let mut indicator = two_account_indicator(&fake).await;
+        indicator.update_streams().await.unwrap();
+        let notifications = record_notifications(&mut indicator);
+
+        fake.state().sessions.remove("partner-token");
+        indicator.update_streams().await.unwrap();
+
+        assert!(indicator.accounts[1].rejected);
+        assert_eq!(indicator.get_current_streams().len(), 3);
+        assert!(indicator.tray_state.borrow().recently_ended.is_empty());
+        assert!(notifications.summaries().is_empty());
+    }
+
+    #[tokio::test]
+    async fn removed_account_forgets_its_tokens() {
+        let fake = FakeTwitch::start();
+        let mut indicator = two_account_indicator(&fake).await;
+
+        indicator.remove_account("Partner").await.unwrap();
+
+        assert_eq!(indicator.accounts.len(), 1);
+        assert!(
+            indicator
+                .credentials
+                .load("partner")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let accounts = indicator.config.read().await.twitch.accounts.clone();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].login, "viewer");
+
+        indicator.update_streams().await.unwrap();
+        assert_eq!(indicator.get_current_streams().len(), 2);
+        assert!(indicator.get_current_streams()[0].followed_by.is_empty());
+    }
+
+    #[tokio::test]
+    async fn exported_settings_contain_no_tokens() {
+        let fake = FakeTwitch::start();
+        let indicator = indicator_for(&fake).await;
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("export.toml");
+
+        indicator
+            .export_settings(path.to_str().unwrap())
+            .await
+            .unwrap();
+
+        let exported = std::fs::read_to_string(&path).unwrap();
+        assert!(exported.contains(CLIENT_ID));
+        assert!(!exported.contains(ACCESS_TOKEN));
+        assert!(!exported.contains("access_token"));
     }
 }
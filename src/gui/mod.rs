@@ -1,7 +1,10 @@
+pub mod accounts;
 pub mod gtk_settings;
 pub mod indicator;
+pub mod notification_filter;
 pub mod notifications;
 pub mod settings;
 pub mod tray;
+pub mod tray_icon;
 
 pub use indicator::TwitchIndicator;
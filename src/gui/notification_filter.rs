@@ -0,0 +1,150 @@
+use regex::{Regex, RegexBuilder};
+use tracing::warn;
+
+use crate::api::models::Stream;
+use crate::config::{NotificationRule, RuleAction};
+
+struct CompiledRule {
+    rule: NotificationRule,
+    title: Option<Regex>,
+}
+
+impl CompiledRule {
+    fn matches(&self, stream: &Stream) -> bool {
+        let rule = &self.rule;
+
+        rule.game_name
+            .as_ref()
+            .is_none_or(|game| game.eq_ignore_ascii_case(&stream.game_name))
+            && rule.game_id.as_ref().is_none_or(|id| *id == stream.game_id)
+            && self
+                .title
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(&stream.title))
+            && rule
+                .language
+                .as_ref()
+                .is_none_or(|language| language.eq_ignore_ascii_case(&stream.language))
+            && rule
+                .is_mature
+                .is_none_or(|mature| mature == stream.is_mature)
+            && rule
+                .min_viewers
+                .is_none_or(|min_viewers| stream.viewer_count >= min_viewers)
+    }
+}
+
+// Exclude rules always win; when include rules exist a stream must match one
+#[derive(Default)]
+pub struct NotificationFilter {
+    include: Vec<CompiledRule>,
+    exclude: Vec<CompiledRule>,
+}
+
+impl NotificationFilter {
+    pub fn new(rules: &[NotificationRule]) -> Self {
+        let mut filter = Self::default();
+
+        for rule in rules {
+            let title = match rule.title_regex.as_deref().map(compile_title_regex) {
+                Some(Ok(regex)) => Some(regex),
+                Some(Err(e)) => {
+                    warn!("Ignoring notification rule with invalid title regex: {}", e);
+                    continue;
+                }
+                None => None,
+            };
+
+            let compiled = CompiledRule {
+                rule: rule.clone(),
+                title,
+            };
+            match rule.action {
+                RuleAction::Include => filter.include.push(compiled),
+                RuleAction::Exclude => filter.exclude.push(compiled),
+            }
+        }
+
+        filter
+    }
+
+    pub fn allows(&self, stream: &Stream) -> bool {
+        if self.exclude.iter().any(|rule| rule.matches(stream)) {
+            return false;
+        }
+
+        self.include.is_empty() || self.include.iter().any(|rule| rule.matches(stream))
+    }
+}
+
+pub fn compile_title_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_twitch::stream;
+
+    fn with(game: &str, title: &str, viewers: u32) -> Stream {
+        let mut stream = stream("s1", "u1", "alpha", viewers);
+        stream.game_name = game.to_string();
+        stream.title = title.to_string();
+        stream
+    }
+
+    #[test]
+    fn include_rules_require_a_match() {
+        let filter = NotificationFilter::new(&[
+            NotificationRule {
+                game_name: Some("counter-strike".to_string()),
+                ..Default::default()
+            },
+            NotificationRule {
+                title_regex: Some(r"\btournament\b".to_string()),
+                ..Default::default()
+            },
+        ]);
+
+        assert!(filter.allows(&with("Counter-Strike", "ranked grind", 10)));
+        assert!(filter.allows(&with("Chess", "Weekly TOURNAMENT finals", 10)));
+        assert!(!filter.allows(&with("Chess", "casual games", 10)));
+    }
+
+    #[test]
+    fn exclude_rules_win_over_include_rules() {
+        let filter = NotificationFilter::new(&[
+            NotificationRule {
+                title_regex: Some("tournament".to_string()),
+                ..Default::default()
+            },
+            NotificationRule {
+                action: RuleAction::Exclude,
+                is_mature: Some(true),
+                ..Default::default()
+            },
+            NotificationRule {
+                action: RuleAction::Exclude,
+                min_viewers: Some(1000),
+                ..Default::default()
+            },
+        ]);
+
+        let mut mature = with("Chess", "tournament", 10);
+        mature.is_mature = true;
+
+        assert!(filter.allows(&with("Chess", "tournament", 10)));
+        assert!(!filter.allows(&mature));
+        assert!(!filter.allows(&with("Chess", "tournament", 5000)));
+    }
+
+    #[test]
+    fn invalid_regex_rules_are_ignored() {
+        let filter = NotificationFilter::new(&[NotificationRule {
+            title_regex: Some("(unclosed".to_string()),
+            ..Default::default()
+        }]);
+
+        assert!(filter.allows(&with("Chess", "anything", 10)));
+    }
+}
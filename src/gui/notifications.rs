@@ -1,40 +1,191 @@
 #![allow(dead_code)]
 
-use anyhow::Result;
-use notify_rust::{Notification, Timeout, Urgency};
-use std::collections::HashSet;
+use anyhow::{Context, Result};
+use chrono::Utc;
+use futures_util::StreamExt;
+use notify_rust::{Notification, NotificationHandle, Timeout, Urgency};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tracing::{debug, error};
+use zbus::{MatchRule, MessageStream};
 
-use crate::api::models::Stream;
-use crate::config::NotificationConfig;
+use crate::api::models::{Stream, format_duration};
+use crate::config::{NotificationConfig, NotificationPolicy};
+use crate::gui::notification_filter::NotificationFilter;
+
+// Servers invoke the default action when the notification itself is clicked
+const ACTION_DEFAULT: &str = "default";
+const ACTION_OPEN_CHAT: &str = "open_chat";
+const ACTION_MUTE: &str = "mute";
+// Passed to the handler when the notification is dismissed instead
+const ACTION_CLOSED: &str = "__closed";
+
+// Called with the action key once a notification is acted on or closed
+pub type ActionHandler = Box<dyn FnOnce(&str) + Send>;
+
+// Where notifications are shown; tests install one that records them instead
+pub trait Notifier: Send + Sync {
+    fn supports_images(&mut self) -> bool;
+    fn show(&mut self, notification: Notification, on_action: ActionHandler) -> Result<()>;
+}
+
+// Notifications waiting for an action, keyed by server id. The handle is kept
+// so the connection the notification was sent on stays open until then
+type PendingActions = Arc<Mutex<HashMap<u32, (NotificationHandle, ActionHandler)>>>;
+
+#[derive(Default)]
+pub struct DesktopNotifier {
+    image_support: Option<bool>,
+    pending: PendingActions,
+    listener: Option<JoinHandle<()>>,
+}
+
+impl DesktopNotifier {
+    // One task listens for the server's signals for every notification, so
+    // nothing blocks a thread and shutdown doesn't wait on open notifications
+    fn ensure_listener(&mut self) {
+        if self.listener.as_ref().is_some_and(|l| !l.is_finished()) {
+            return;
+        }
+        let pending = self.pending.clone();
+        self.listener = Some(tokio::spawn(async move {
+            if let Err(e) = listen_for_actions(&pending).await {
+                error!("Stopped listening for notification actions: {:#}", e);
+            }
+        }));
+    }
+}
+
+async fn listen_for_actions(pending: &PendingActions) -> Result<()> {
+    let connection = zbus::Connection::session()
+        .await
+        .context("Failed to connect to the session bus")?;
+    let rule = MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.Notifications")?
+        .build();
+    let mut signals = MessageStream::for_match_rule(rule, &connection, None).await?;
+
+    while let Some(message) = signals.next().await {
+        let message = message?;
+        let header = message.header();
+        let (id, action) = match header.member().map(|m| m.as_str()) {
+            Some("ActionInvoked") => message.body().deserialize::<(u32, String)>()?,
+            Some("NotificationClosed") => {
+                let (id, _reason) = message.body().deserialize::<(u32, u32)>()?;
+                (id, ACTION_CLOSED.to_string())
+            }
+            _ => continue,
+        };
+        let entry = pending.lock().unwrap().remove(&id);
+        if let Some((_handle, on_action)) = entry {
+            on_action(&action);
+        }
+    }
+    Ok(())
+}
+
+impl Notifier for DesktopNotifier {
+    fn supports_images(&mut self) -> bool {
+        *self
+            .image_support
+            .get_or_insert_with(|| match notify_rust::get_capabilities() {
+                Ok(capabilities) => capabilities
+                    .iter()
+                    .any(|c| matches!(c.as_str(), "icon-static" | "icon-multi" | "body-images")),
+                Err(e) => {
+                    debug!("Failed to query notification server capabilities: {}", e);
+                    false
+                }
+            })
+    }
+
+    fn show(&mut self, notification: Notification, on_action: ActionHandler) -> Result<()> {
+        self.ensure_listener();
+        let handle = notification
+            .show()
+            .map_err(|e| anyhow::anyhow!("Failed to show notification: {}", e))?;
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(handle.id(), (handle, on_action));
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum NotificationAction {
+    OpenStream { url: String },
+    OpenChat { login: String },
+    Mute { user_id: String, user_name: String },
+}
 
 pub struct NotificationManager {
     config: NotificationConfig,
-    shown_streams: HashSet<String>,
+    filter: NotificationFilter,
+    shown_streams: HashMap<String, Stream>,
+    avatars: HashMap<String, PathBuf>,
+    notifier: Box<dyn Notifier>,
+    action_tx: Option<mpsc::UnboundedSender<NotificationAction>>,
 }
 
 impl NotificationManager {
     pub fn new(config: NotificationConfig) -> Self {
         Self {
+            filter: NotificationFilter::new(&config.rules),
             config,
-            shown_streams: HashSet::new(),
+            shown_streams: HashMap::new(),
+            avatars: HashMap::new(),
+            notifier: Box::new(DesktopNotifier::default()),
+            action_tx: None,
         }
     }
 
+    pub fn set_notifier(&mut self, notifier: Box<dyn Notifier>) {
+        self.notifier = notifier;
+    }
+
     pub fn update_config(&mut self, config: NotificationConfig) {
+        self.filter = NotificationFilter::new(&config.rules);
         self.config = config;
     }
 
-    pub fn notify_new_streams(&mut self, streams: &[Stream]) -> Result<()> {
-        if !self.config.enabled {
-            debug!("Notifications disabled, skipping");
-            return Ok(());
-        }
+    pub fn set_avatar(&mut self, user_id: &str, path: PathBuf) {
+        self.avatars.insert(user_id.to_string(), path);
+    }
+
+    pub fn has_avatar(&self, user_id: &str) -> bool {
+        self.avatars.get(user_id).is_some_and(|path| path.exists())
+    }
 
-        let new_streams: Vec<&Stream> = streams
+    // The image cache may have evicted the file since it was recorded
+    pub fn avatar_is_stale(&self, user_id: &str) -> bool {
+        self.avatars.get(user_id).is_some_and(|path| !path.exists())
+    }
+
+    pub fn is_tracked(&self, stream_id: &str) -> bool {
+        self.shown_streams.contains_key(stream_id)
+    }
+
+    pub fn set_action_sender(&mut self, sender: mpsc::UnboundedSender<NotificationAction>) {
+        self.action_tx = Some(sender);
+    }
+
+    // Streams suppressed by policy are still tracked so they are not announced
+    // later in the same broadcast when the policy or global toggle changes
+    pub fn notify_new_streams(&mut self, streams: &[Stream]) -> Result<()> {
+        let (new_streams, suppressed): (Vec<&Stream>, Vec<&Stream>) = streams
             .iter()
-            .filter(|stream| !self.shown_streams.contains(&stream.id))
-            .collect();
+            .filter(|stream| !self.shown_streams.contains_key(&stream.id))
+            .partition(|stream| self.should_notify(stream));
+
+        for stream in suppressed {
+            debug!("Notifications disabled for {}, skipping", stream.user_name);
+            self.shown_streams.insert(stream.id.clone(), stream.clone());
+        }
 
         if new_streams.is_empty() {
             return Ok(());
@@ -52,20 +203,76 @@ impl NotificationManager {
                     stream.user_name, e
                 );
             } else {
-                self.shown_streams.insert(stream.id.clone());
+                self.shown_streams.insert(stream.id.clone(), stream.clone());
             }
         }
 
         Ok(())
     }
 
+    fn should_notify(&self, stream: &Stream) -> bool {
+        match self.config.channel_policy(&stream.user_id) {
+            NotificationPolicy::Always => true,
+            NotificationPolicy::Never => false,
+            NotificationPolicy::Default => self.config.enabled && self.filter.allows(stream),
+        }
+    }
+
     pub fn update_live_streams(&mut self, current_streams: &[Stream]) {
         let current_ids: HashSet<String> = current_streams.iter().map(|s| s.id.clone()).collect();
 
-        self.shown_streams.retain(|id| current_ids.contains(id));
+        let ended: Vec<Stream> = self
+            .shown_streams
+            .extract_if(|id, _| !current_ids.contains(id))
+            .map(|(_, stream)| stream)
+            .collect();
+
+        if !self.config.notify_offline {
+            return;
+        }
+
+        for stream in ended {
+            if !self.should_notify(&stream) {
+                continue;
+            }
+
+            if let Err(e) = self.show_offline_notification(&stream) {
+                error!(
+                    "Failed to show offline notification for {}: {}",
+                    stream.user_name, e
+                );
+            }
+        }
     }
 
-    fn show_stream_notification(&self, stream: &Stream) -> Result<()> {
+    pub fn notify_stream_changes(&mut self, streams: &[Stream]) -> Result<()> {
+        for stream in streams {
+            let Some(previous) = self.shown_streams.get(&stream.id) else {
+                continue;
+            };
+
+            let category_changed =
+                self.config.notify_category_change && previous.game_id != stream.game_id;
+            let title_changed = self.config.notify_title_change && previous.title != stream.title;
+
+            self.shown_streams.insert(stream.id.clone(), stream.clone());
+
+            if !(category_changed || title_changed) || !self.should_notify(stream) {
+                continue;
+            }
+
+            if let Err(e) = self.show_change_notification(stream, category_changed, title_changed) {
+                error!(
+                    "Failed to show change notification for {}: {}",
+                    stream.user_name, e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn show_stream_notification(&mut self, stream: &Stream) -> Result<()> {
         let title = format!("{} is now live!", stream.user_name);
         let mut body = stream.title.clone();
 
@@ -77,6 +284,10 @@ impl NotificationManager {
             body.push_str(&format!("\nViewers: {}", stream.formatted_viewer_count()));
         }
 
+        if self.config.show_uptime {
+            body.push_str(&format!("\nUptime: {}", stream.formatted_uptime()));
+        }
+
         let mut notification = Notification::new();
         notification
             .summary(&title)
@@ -84,10 +295,10 @@ impl NotificationManager {
             .icon("twitch")
             .timeout(Timeout::Milliseconds(self.config.timeout_ms))
             .urgency(Urgency::Normal);
+        add_stream_actions(&mut notification);
+        self.apply_avatar(&mut notification, stream);
 
-        let _handle = notification
-            .show()
-            .map_err(|e| anyhow::anyhow!("Failed to show notification: {}", e))?;
+        self.deliver(notification, stream)?;
 
         debug!(
             "Showed notification for stream: {} ({})",
@@ -97,6 +308,115 @@ impl NotificationManager {
         Ok(())
     }
 
+    fn show_change_notification(
+        &mut self,
+        stream: &Stream,
+        category_changed: bool,
+        title_changed: bool,
+    ) -> Result<()> {
+        let change = match (category_changed, title_changed) {
+            (true, true) => "category and title",
+            (true, false) => "category",
+            _ => "title",
+        };
+        let title = format!("{} changed {}", stream.user_name, change);
+
+        let mut body = stream.title.clone();
+        if category_changed && !stream.game_name.is_empty() {
+            body.push_str(&format!("\n\nNow playing: {}", stream.game_name));
+        }
+
+        let mut notification = Notification::new();
+        notification
+            .summary(&title)
+            .body(&body)
+            .icon("twitch")
+            .timeout(Timeout::Milliseconds(self.config.timeout_ms))
+            .urgency(Urgency::Low);
+        add_stream_actions(&mut notification);
+        self.apply_avatar(&mut notification, stream);
+
+        self.deliver(notification, stream)?;
+
+        debug!(
+            "Showed {} change notification for stream: {} ({})",
+            change, stream.user_name, stream.id
+        );
+
+        Ok(())
+    }
+
+    fn show_offline_notification(&mut self, stream: &Stream) -> Result<()> {
+        let title = format!("{} went offline", stream.user_name);
+        let body = match stream.live_duration(Utc::now()) {
+            Some(duration) => format!("Streamed for {}", format_duration(duration)),
+            None => "Stream ended".to_string(),
+        };
+
+        let mut notification = Notification::new();
+        notification
+            .summary(&title)
+            .body(&body)
+            .icon("twitch")
+            .timeout(Timeout::Milliseconds(self.config.timeout_ms))
+            .urgency(Urgency::Low)
+            .action(ACTION_OPEN_CHAT, "Open chat");
+        self.apply_avatar(&mut notification, stream);
+
+        self.deliver(notification, stream)?;
+
+        debug!(
+            "Showed offline notification for stream: {} ({})",
+            stream.user_name, stream.id
+        );
+
+        Ok(())
+    }
+
+    // The generic icon stays set so servers without image support still show it
+    fn apply_avatar(&mut self, notification: &mut Notification, stream: &Stream) {
+        let Some(path) = self.avatars.get(&stream.user_id).cloned() else {
+            return;
+        };
+        if !path.exists() {
+            return;
+        }
+
+        if self.notifier.supports_images() {
+            notification.image_path(&path.to_string_lossy());
+        }
+    }
+
+    fn deliver(&mut self, notification: Notification, stream: &Stream) -> Result<()> {
+        let sender = self.action_tx.clone();
+        let stream = stream.clone();
+
+        self.notifier.show(
+            notification,
+            Box::new(move |action| {
+                if let Some(sender) = sender
+                    && let Some(action) = Self::map_action(action, &stream)
+                {
+                    let _ = sender.send(action);
+                }
+            }),
+        )
+    }
+
+    fn map_action(action: &str, stream: &Stream) -> Option<NotificationAction> {
+        match action {
+            ACTION_DEFAULT => Some(NotificationAction::OpenStream { url: stream.url() }),
+            ACTION_OPEN_CHAT => Some(NotificationAction::OpenChat {
+                login: stream.user_login.clone(),
+            }),
+            ACTION_MUTE => Some(NotificationAction::Mute {
+                user_id: stream.user_id.clone(),
+                user_name: stream.user_name.clone(),
+            }),
+            _ => None,
+        }
+    }
+
     pub fn clear_tracked_streams(&mut self) {
         self.shown_streams.clear();
         debug!("Cleared all tracked streams");
@@ -106,3 +426,226 @@ impl NotificationManager {
         self.shown_streams.len()
     }
 }
+
+fn add_stream_actions(notification: &mut Notification) {
+    notification
+        .action(ACTION_DEFAULT, "Open stream")
+        .action(ACTION_OPEN_CHAT, "Open chat")
+        .action(ACTION_MUTE, "Mute channel");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, NotificationPolicy, NotificationRule, RuleAction};
+    use crate::testing::RecordingNotifier;
+    use crate::testing::fake_twitch::stream;
+
+    fn manager_with(
+        enabled: bool,
+        policies: &[(&str, NotificationPolicy)],
+    ) -> (NotificationManager, RecordingNotifier) {
+        let mut config = Config::default().notifications;
+        config.enabled = enabled;
+        for (user_id, policy) in policies {
+            config.channel_policies.insert(user_id.to_string(), *policy);
+        }
+
+        let notifier = RecordingNotifier::new();
+        let mut manager = NotificationManager::new(config);
+        manager.set_notifier(Box::new(notifier.clone()));
+        (manager, notifier)
+    }
+
+    #[test]
+    fn channel_policies_override_global_setting() {
+        let streams = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 20),
+            stream("s3", "u3", "gamma", 30),
+        ];
+
+        let (mut manager, notifier) = manager_with(
+            true,
+            &[
+                ("u1", NotificationPolicy::Never),
+                ("u2", NotificationPolicy::Always),
+            ],
+        );
+        manager.notify_new_streams(&streams).unwrap();
+        assert_eq!(
+            notifier.summaries(),
+            ["beta is now live!", "gamma is now live!"]
+        );
+
+        let (mut manager, notifier) = manager_with(false, &[("u2", NotificationPolicy::Always)]);
+        manager.notify_new_streams(&streams).unwrap();
+        assert_eq!(notifier.summaries(), ["beta is now live!"]);
+    }
+
+    #[test]
+    fn rules_filter_default_channels_only() {
+        let mut chess = stream("s1", "u1", "alpha", 10);
+        chess.game_name = "Chess".to_string();
+        let streams = vec![chess, stream("s2", "u2", "beta", 20)];
+
+        let (mut manager, notifier) = manager_with(true, &[("u2", NotificationPolicy::Always)]);
+        let mut config = manager.config.clone();
+        config.rules = vec![NotificationRule {
+            game_name: Some("chess".to_string()),
+            ..Default::default()
+        }];
+        manager.update_config(config);
+
+        manager.notify_new_streams(&streams).unwrap();
+        assert_eq!(
+            notifier.summaries(),
+            ["alpha is now live!", "beta is now live!"]
+        );
+
+        let mut config = manager.config.clone();
+        config.rules[0].action = RuleAction::Exclude;
+        manager.update_config(config);
+        manager.update_live_streams(&[]);
+
+        manager.notify_new_streams(&streams).unwrap();
+        assert_eq!(notifier.summaries(), ["beta is now live!"]);
+    }
+
+    #[test]
+    fn category_and_title_changes_are_notified_when_enabled() {
+        let (mut manager, notifier) = manager_with(true, &[]);
+        let mut live = vec![stream("s1", "u1", "alpha", 10)];
+        manager.notify_new_streams(&live).unwrap();
+        notifier.summaries();
+
+        live[0].game_id = "32399".to_string();
+        live[0].game_name = "Counter-Strike".to_string();
+        manager.notify_stream_changes(&live).unwrap();
+        assert!(notifier.summaries().is_empty());
+
+        let mut config = manager.config.clone();
+        config.notify_category_change = true;
+        config.notify_title_change = true;
+        manager.update_config(config);
+
+        live[0].game_id = "743".to_string();
+        live[0].game_name = "Chess".to_string();
+        live[0].title = "Finals".to_string();
+        manager.notify_stream_changes(&live).unwrap();
+        assert_eq!(notifier.summaries(), ["alpha changed category and title"]);
+
+        manager.notify_stream_changes(&live).unwrap();
+        assert!(notifier.summaries().is_empty());
+
+        live[0].title = "Grand finals".to_string();
+        manager.notify_stream_changes(&live).unwrap();
+        assert_eq!(notifier.summaries(), ["alpha changed title"]);
+    }
+
+    #[test]
+    fn change_notifications_honour_rules_and_policies() {
+        let (mut manager, notifier) = manager_with(true, &[("u2", NotificationPolicy::Never)]);
+        let mut config = manager.config.clone();
+        config.notify_category_change = true;
+        config.rules = vec![NotificationRule {
+            game_name: Some("Counter-Strike".to_string()),
+            ..Default::default()
+        }];
+        manager.update_config(config);
+
+        let mut live = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 10),
+        ];
+        manager.notify_new_streams(&live).unwrap();
+        assert!(notifier.summaries().is_empty());
+
+        for stream in &mut live {
+            stream.game_id = "32399".to_string();
+            stream.game_name = "Counter-Strike".to_string();
+        }
+        manager.notify_stream_changes(&live).unwrap();
+        assert_eq!(notifier.summaries(), ["alpha changed category"]);
+    }
+
+    #[test]
+    fn ended_streams_are_notified_when_enabled() {
+        let (mut manager, notifier) = manager_with(true, &[("u2", NotificationPolicy::Never)]);
+        let live = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 10),
+        ];
+        manager.notify_new_streams(&live).unwrap();
+        notifier.summaries();
+
+        manager.update_live_streams(&live[1..]);
+        assert!(notifier.summaries().is_empty());
+
+        let mut config = manager.config.clone();
+        config.notify_offline = true;
+        manager.update_config(config);
+        manager.notify_new_streams(&live).unwrap();
+        notifier.summaries();
+
+        manager.update_live_streams(&[]);
+        let delivered: Vec<(String, String)> = notifier
+            .take()
+            .into_iter()
+            .map(|n| (n.summary, n.body))
+            .collect();
+        assert_eq!(delivered.len(), 1);
+        assert_eq!(delivered[0].0, "alpha went offline");
+        assert!(delivered[0].1.starts_with("Streamed for "));
+    }
+
+    #[test]
+    fn stream_notifications_offer_each_action_once() {
+        let (mut manager, notifier) = manager_with(true, &[]);
+        let (action_tx, mut action_rx) = mpsc::unbounded_channel();
+        manager.set_action_sender(action_tx);
+        manager
+            .notify_new_streams(&[stream("s1", "u1", "alpha", 10)])
+            .unwrap();
+
+        let actions = notifier.take().remove(0).actions;
+        assert_eq!(
+            actions,
+            [
+                ACTION_DEFAULT,
+                "Open stream",
+                ACTION_OPEN_CHAT,
+                "Open chat",
+                ACTION_MUTE,
+                "Mute channel"
+            ]
+        );
+
+        notifier.invoke(ACTION_DEFAULT);
+        assert!(matches!(
+            action_rx.try_recv(),
+            Ok(NotificationAction::OpenStream { url }) if url.ends_with("/alpha")
+        ));
+    }
+
+    #[test]
+    fn suppressed_streams_are_tracked_until_they_end() {
+        let streams = vec![stream("s1", "u1", "alpha", 10)];
+        let (mut manager, notifier) = manager_with(true, &[("u1", NotificationPolicy::Never)]);
+
+        manager.notify_new_streams(&streams).unwrap();
+        assert!(notifier.summaries().is_empty());
+        assert_eq!(manager.tracked_stream_count(), 1);
+
+        manager.update_config(Config::default().notifications);
+        manager.notify_new_streams(&streams).unwrap();
+        assert!(notifier.summaries().is_empty());
+
+        manager.update_live_streams(&[]);
+        assert_eq!(manager.tracked_stream_count(), 0);
+
+        let restarted = vec![stream("s2", "u1", "alpha", 10)];
+        manager.notify_new_streams(&restarted).unwrap();
+        assert_eq!(notifier.summaries(), ["alpha is now live!"]);
+    }
+}
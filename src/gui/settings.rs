@@ -1,19 +1,23 @@
 #![allow(dead_code)]
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info};
 
-use crate::config::{Config, GeneralConfig, NotificationConfig, TwitchConfig, UiConfig};
+use crate::config::{
+    AppDirs, Autostart, Config, GeneralConfig, NotificationConfig, TwitchConfig, UiConfig,
+};
+use crate::gui::notification_filter::compile_title_regex;
 
 pub struct SettingsWindow {
     config: Arc<RwLock<Config>>,
+    dirs: AppDirs,
     temp_config: Config,
 }
 
 impl SettingsWindow {
-    pub async fn new(config: Arc<RwLock<Config>>) -> Result<Self> {
+    pub async fn new(config: Arc<RwLock<Config>>, dirs: AppDirs) -> Result<Self> {
         let temp_config = {
             let config_guard = config.read().await;
             config_guard.clone()
@@ -21,6 +25,7 @@ impl SettingsWindow {
 
         Ok(Self {
             config,
+            dirs,
             temp_config,
         })
     }
@@ -183,8 +188,11 @@ impl SettingsWindow {
         let mut config_guard = self.config.write().await;
         *config_guard = self.temp_config.clone();
 
-        let config_path = Config::get_config_dir()?.join("config.toml");
-        config_guard.save(&config_path).await?;
+        config_guard.save_default(&self.dirs).await?;
+
+        Autostart::for_current_exe(&self.dirs)?
+            .set_enabled(config_guard.general.autostart)
+            .await?;
 
         info!("Settings saved successfully");
         Ok(())
@@ -251,6 +259,13 @@ impl SettingsWindow {
             return Err(anyhow::anyhow!("Twitch Client ID cannot be empty"));
         }
 
+        for rule in &self.temp_config.notifications.rules {
+            if let Some(pattern) = &rule.title_regex {
+                compile_title_regex(pattern)
+                    .with_context(|| format!("Invalid notification rule title regex: {pattern}"))?;
+            }
+        }
+
         Ok(())
     }
 }
@@ -260,17 +275,21 @@ impl SettingsWindow {
         self.temp_config.notifications.enabled = false;
         self.temp_config.notifications.show_game = false;
         self.temp_config.notifications.show_viewer_count = false;
+        self.temp_config.notifications.show_uptime = false;
         self.temp_config.twitch.refresh_interval_minutes = 5;
         self.temp_config.ui.show_selected_channels_on_top = false;
+        self.temp_config.ui.show_uptime = false;
     }
 
     pub fn apply_full_preset(&mut self) {
         self.temp_config.notifications.enabled = true;
         self.temp_config.notifications.show_game = true;
         self.temp_config.notifications.show_viewer_count = true;
+        self.temp_config.notifications.show_uptime = true;
         self.temp_config.notifications.timeout_ms = 5000;
         self.temp_config.twitch.refresh_interval_minutes = 1;
         self.temp_config.ui.show_selected_channels_on_top = true;
+        self.temp_config.ui.show_uptime = true;
     }
 
     pub fn apply_privacy_preset(&mut self) {
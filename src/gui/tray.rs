@@ -1,10 +1,12 @@
 #![allow(dead_code)]
 
 use anyhow::Result;
+use chrono::{DateTime, Local, TimeDelta};
 
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 #[cfg(target_os = "linux")]
 use libappindicator::{AppIndicator, AppIndicatorStatus};
@@ -12,19 +14,107 @@ use libappindicator::{AppIndicator, AppIndicatorStatus};
 #[cfg(target_os = "linux")]
 use gtk::prelude::*;
 
-use crate::api::models::Stream;
-use crate::config::Config;
+use crate::api::models::{Stream, format_duration};
+use crate::config::{AppDirs, Config, FavouriteChannel, MenuLayout};
+use crate::gui::tray_icon;
+
+#[derive(Debug, Clone, Default)]
+pub struct TrayState {
+    pub streams: Vec<Stream>,
+    pub tooltip: String,
+    pub status: TrayStatus,
+    pub favourites: Vec<FavouriteChannel>,
+    pub pin_favourites: bool,
+    pub recently_ended: Vec<EndedStream>,
+    pub show_uptime: bool,
+    pub badge_count: Option<usize>,
+    pub menu_layout: MenuLayout,
+}
+
+#[derive(Debug, Clone)]
+pub struct GameGroup {
+    pub game_name: String,
+    pub total_viewers: u64,
+    pub streams: Vec<Stream>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EndedStream {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub ended_at: DateTime<Local>,
+    pub duration: Option<TimeDelta>,
+}
+
+impl EndedStream {
+    pub fn new(stream: &Stream, ended_at: DateTime<Local>) -> Self {
+        Self {
+            user_id: stream.user_id.clone(),
+            user_login: stream.user_login.clone(),
+            user_name: stream.user_name.clone(),
+            ended_at,
+            duration: stream.live_duration(ended_at.to_utc()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        let ended = self.ended_at.format("%H:%M");
+        match self.duration {
+            Some(duration) => format!(
+                "{} (ended {}, {})",
+                self.user_name,
+                ended,
+                format_duration(duration)
+            ),
+            None => format!("{} (ended {})", self.user_name, ended),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum TrayStatus {
+    #[default]
+    Ok,
+    Refreshing,
+    Offline {
+        retry_in: Duration,
+    },
+    Error(String),
+    // Twitch refused these accounts' tokens; they are offered a new sign-in
+    NeedsSignIn(Vec<RejectedAccount>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectedAccount {
+    pub account_id: String,
+    pub name: String,
+}
+
+fn needs_sign_in_label(accounts: &[RejectedAccount]) -> String {
+    let names: Vec<&str> = accounts.iter().map(|a| a.name.as_str()).collect();
+    format!("{} needs to sign in again", names.join(", "))
+}
 
 pub struct SystemTray {
     #[cfg(target_os = "linux")]
     indicator: AppIndicator,
     config: Arc<RwLock<Config>>,
     streams: Vec<Stream>,
+    status: TrayStatus,
+    favourites: Vec<FavouriteChannel>,
+    pin_favourites: bool,
+    recently_ended: Vec<EndedStream>,
+    show_uptime: bool,
+    icon_dir: std::path::PathBuf,
+    badge_count: Option<usize>,
+    menu_layout: MenuLayout,
     shutdown_tx: Option<tokio::sync::watch::Sender<bool>>,
+    action_tx: Option<tokio::sync::mpsc::UnboundedSender<String>>,
 }
 
 impl SystemTray {
-    pub fn new(config: Arc<RwLock<Config>>) -> Result<Self> {
+    pub fn new(config: Arc<RwLock<Config>>, dirs: &AppDirs) -> Result<Self> {
         #[cfg(target_os = "linux")]
         {
             let mut indicator = AppIndicator::new(
@@ -32,13 +122,7 @@ impl SystemTray {
                 "network-wireless", // More visible network icon
             );
 
-            let icon_path = std::path::Path::new("assets/twitch-icon.png");
-            if icon_path.exists() {
-                indicator.set_icon_theme_path("assets");
-                indicator.set_icon_full("twitch-icon", "Twitch Indicator");
-            } else {
-                indicator.set_icon_full("applications-internet", "Twitch Indicator");
-            }
+            Self::set_default_icon(&mut indicator);
 
             indicator.set_status(AppIndicatorStatus::Active);
             indicator.set_title("Twitch Indicator");
@@ -50,7 +134,16 @@ impl SystemTray {
                 indicator,
                 config,
                 streams: Vec::new(),
+                status: TrayStatus::Ok,
+                favourites: Vec::new(),
+                pin_favourites: false,
+                recently_ended: Vec::new(),
+                show_uptime: false,
+                icon_dir: tray_icon::icon_dir(dirs),
+                badge_count: None,
+                menu_layout: MenuLayout::Flat,
                 shutdown_tx: None,
+                action_tx: None,
             })
         }
 
@@ -92,51 +185,155 @@ impl SystemTray {
         self.rebuild_menu()
     }
 
+    pub fn apply_state(&mut self, state: TrayState) -> Result<()> {
+        self.streams = state.streams;
+        self.status = state.status;
+        self.favourites = state.favourites;
+        self.pin_favourites = state.pin_favourites;
+        self.recently_ended = state.recently_ended;
+        self.show_uptime = state.show_uptime;
+        self.menu_layout = state.menu_layout;
+        self.update_icon(state.badge_count);
+        self.set_tooltip(&state.tooltip)?;
+        self.rebuild_menu()
+    }
+
+    #[cfg(target_os = "linux")]
+    fn set_default_icon(indicator: &mut AppIndicator) {
+        let icon_path = std::path::Path::new("assets/twitch-icon.png");
+        if icon_path.exists() {
+            indicator.set_icon_theme_path("assets");
+            indicator.set_icon_full("twitch-icon", "Twitch Indicator");
+        } else {
+            indicator.set_icon_full("applications-internet", "Twitch Indicator");
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    fn update_icon(&mut self, badge_count: Option<usize>) {
+        if badge_count == self.badge_count {
+            return;
+        }
+        self.badge_count = badge_count;
+
+        let Some(count) = badge_count else {
+            Self::set_default_icon(&mut self.indicator);
+            return;
+        };
+        let dir = &self.icon_dir;
+
+        match tray_icon::write_badge_icon(dir, count) {
+            Ok(name) => {
+                self.indicator.set_icon_theme_path(&dir.to_string_lossy());
+                self.indicator
+                    .set_icon_full(&name, &format!("{count} live streams"));
+            }
+            Err(e) => {
+                error!("Failed to render tray icon: {e}");
+                Self::set_default_icon(&mut self.indicator);
+            }
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn update_icon(&mut self, badge_count: Option<usize>) {
+        self.badge_count = badge_count;
+    }
+
     #[cfg(target_os = "linux")]
     fn rebuild_menu(&mut self) -> Result<()> {
         let mut menu = gtk::Menu::new();
 
+        let status_label = match &self.status {
+            TrayStatus::Ok => None,
+            TrayStatus::Refreshing => Some("Refreshing…".to_string()),
+            TrayStatus::Offline { retry_in } => Some(offline_label(*retry_in)),
+            TrayStatus::Error(message) => Some(format!("Update failed: {message}")),
+            TrayStatus::NeedsSignIn(accounts) => Some(needs_sign_in_label(accounts)),
+        };
+
+        if let Some(status_label) = status_label {
+            let status_item = gtk::MenuItem::with_label(&status_label);
+            status_item.set_sensitive(false);
+            menu.append(&status_item);
+
+            if let TrayStatus::NeedsSignIn(accounts) = &self.status {
+                for account in accounts {
+                    let sign_in_item =
+                        gtk::MenuItem::with_label(&format!("Sign in {}…", account.name));
+                    let action = format!("sign-in:{}", account.account_id);
+                    let action_sender = self.action_tx.clone();
+                    sign_in_item.connect_activate(move |_| {
+                        if let Some(sender) = &action_sender {
+                            let _ = sender.send(action.clone());
+                        }
+                    });
+                    menu.append(&sign_in_item);
+                }
+            }
+
+            menu.append(&gtk::SeparatorMenuItem::new());
+        }
+
         if self.streams.is_empty() {
             let no_streams_item = gtk::MenuItem::with_label("No live streams");
             no_streams_item.set_sensitive(false);
             menu.append(&no_streams_item);
         } else {
-            let mut sorted_streams = self.streams.clone();
-            sorted_streams.sort_by(|a, b| b.viewer_count.cmp(&a.viewer_count));
-
-            for stream in &sorted_streams {
-                let label = format!("{} ({})", stream.user_name, stream.formatted_viewer_count());
-
-                let stream_item = gtk::MenuItem::with_label(&label);
-
-                let url = stream.url();
-                let config_clone = self.config.clone();
-                stream_item.connect_activate(move |_| {
-                    let url = url.clone();
-                    let config = config_clone.clone();
-
-                    tokio::spawn(async move {
-                        match crate::config::Config::load_or_create(None).await {
-                            Ok(fresh_config) => {
-                                if let Err(e) = fresh_config.open_stream_url(&url) {
-                                    error!("Failed to open stream: {e}");
-                                }
-                            }
-                            Err(e) => {
-                                error!("Failed to reload config ({}), using cached version", e);
-                                let config_guard = config.read().await;
-                                if let Err(e) = config_guard.open_stream_url(&url) {
-                                    error!("Failed to open stream: {e}");
-                                }
-                            }
-                        }
-                    });
-                });
+            let grouped = self.menu_layout == MenuLayout::ByGame;
+            let (pinned, others) = arrange_streams(
+                &self.streams,
+                &self.favourites,
+                self.pin_favourites || grouped,
+            );
 
-                menu.append(&stream_item);
+            if !pinned.is_empty() {
+                let favourites_header = gtk::MenuItem::with_label("Favourites");
+                favourites_header.set_sensitive(false);
+                menu.append(&favourites_header);
+
+                for stream in &pinned {
+                    menu.append(&self.stream_menu_item(stream));
+                }
+
+                if !others.is_empty() {
+                    menu.append(&gtk::SeparatorMenuItem::new());
+                }
+            }
+
+            if grouped {
+                for group in group_by_game(others) {
+                    let group_item = gtk::MenuItem::with_label(&format!(
+                        "{} ({})",
+                        group.game_name,
+                        group.streams.len()
+                    ));
+                    let group_menu = gtk::Menu::new();
+                    for stream in &group.streams {
+                        group_menu.append(&self.stream_menu_item(stream));
+                    }
+                    group_item.set_submenu(Some(&group_menu));
+                    menu.append(&group_item);
+                }
+            } else {
+                for stream in &others {
+                    menu.append(&self.stream_menu_item(stream));
+                }
             }
         }
 
+        menu.append(&gtk::SeparatorMenuItem::new());
+
+        if !self.recently_ended.is_empty() {
+            let recently_ended_item = gtk::MenuItem::with_label("Recently ended");
+            recently_ended_item.set_submenu(Some(&self.recently_ended_menu()));
+            menu.append(&recently_ended_item);
+        }
+
+        let favourites_item = gtk::MenuItem::with_label("Favourites");
+        favourites_item.set_submenu(Some(&self.favourites_menu()));
+        menu.append(&favourites_item);
+
         let separator = gtk::SeparatorMenuItem::new();
         menu.append(&separator);
 
@@ -164,8 +361,13 @@ impl SystemTray {
         menu.append(&settings_item);
 
         let refresh_item = gtk::MenuItem::with_label("Refresh");
+        refresh_item.set_sensitive(self.status != TrayStatus::Refreshing);
+        let action_sender = self.action_tx.clone();
         refresh_item.connect_activate(move |_| {
             info!("Manual refresh requested");
+            if let Some(sender) = &action_sender {
+                let _ = sender.send("refresh".to_string());
+            }
         });
         menu.append(&refresh_item);
 
@@ -191,12 +393,133 @@ impl SystemTray {
         Ok(())
     }
 
+    #[cfg(target_os = "linux")]
+    fn stream_menu_item(&self, stream: &Stream) -> gtk::MenuItem {
+        let marker = if !self.pin_favourites && self.is_favourite(&stream.user_id) {
+            "★ "
+        } else {
+            ""
+        };
+        let label = if self.show_uptime {
+            format!(
+                "{}{} ({}, {}){}",
+                marker,
+                stream.user_name,
+                stream.formatted_viewer_count(),
+                stream.formatted_uptime(),
+                stream.followed_by_suffix()
+            )
+        } else {
+            format!(
+                "{}{} ({}){}",
+                marker,
+                stream.user_name,
+                stream.formatted_viewer_count(),
+                stream.followed_by_suffix()
+            )
+        };
+
+        let stream_item = gtk::MenuItem::with_label(&label);
+
+        let url = stream.url();
+        let config_clone = self.config.clone();
+        stream_item.connect_activate(move |_| {
+            let url = url.clone();
+            let config = config_clone.clone();
+
+            tokio::spawn(async move {
+                let config_guard = config.read().await;
+                if let Err(e) = config_guard.open_stream_url(&url) {
+                    error!("Failed to open stream: {e}");
+                }
+            });
+        });
+
+        stream_item
+    }
+
+    #[cfg(target_os = "linux")]
+    fn recently_ended_menu(&self) -> gtk::Menu {
+        let menu = gtk::Menu::new();
+
+        for ended in &self.recently_ended {
+            let item = gtk::MenuItem::with_label(&ended.label());
+
+            let url = format!("https://www.twitch.tv/{}", ended.user_login);
+            let config_clone = self.config.clone();
+            item.connect_activate(move |_| {
+                let url = url.clone();
+                let config = config_clone.clone();
+
+                tokio::spawn(async move {
+                    let config_guard = config.read().await;
+                    if let Err(e) = config_guard.open_stream_url(&url) {
+                        error!("Failed to open channel: {e}");
+                    }
+                });
+            });
+            menu.append(&item);
+        }
+
+        menu
+    }
+
+    #[cfg(target_os = "linux")]
+    fn favourites_menu(&self) -> gtk::Menu {
+        let menu = gtk::Menu::new();
+
+        let mut channels: Vec<FavouriteChannel> = self.favourites.clone();
+        for stream in &self.streams {
+            if !self.is_favourite(&stream.user_id) {
+                channels.push(FavouriteChannel {
+                    broadcaster_id: stream.user_id.clone(),
+                    broadcaster_name: stream.user_name.clone(),
+                });
+            }
+        }
+        channels.sort_by_key(|c| c.broadcaster_name.to_lowercase());
+
+        if channels.is_empty() {
+            let empty_item = gtk::MenuItem::with_label("No channels");
+            empty_item.set_sensitive(false);
+            menu.append(&empty_item);
+        }
+
+        for channel in channels {
+            let item = gtk::CheckMenuItem::with_label(&channel.broadcaster_name);
+            item.set_active(self.is_favourite(&channel.broadcaster_id));
+
+            let action_sender = self.action_tx.clone();
+            item.connect_toggled(move |_| {
+                if let Some(sender) = &action_sender {
+                    let _ = sender.send(format!(
+                        "favourite:{}:{}",
+                        channel.broadcaster_id, channel.broadcaster_name
+                    ));
+                }
+            });
+            menu.append(&item);
+        }
+
+        menu
+    }
+
+    fn is_favourite(&self, broadcaster_id: &str) -> bool {
+        self.favourites
+            .iter()
+            .any(|c| c.broadcaster_id == broadcaster_id)
+    }
+
     #[cfg(not(target_os = "linux"))]
     fn rebuild_menu(&mut self) -> Result<()> {
         Ok(())
     }
 
-    pub async fn run<F>(mut self, _menu_handler: F) -> Result<()>
+    pub async fn run<F>(
+        mut self,
+        mut state_rx: tokio::sync::watch::Receiver<TrayState>,
+        mut menu_handler: F,
+    ) -> Result<()>
     where
         F: FnMut(String) + Send + 'static,
     {
@@ -207,6 +530,9 @@ impl SystemTray {
             let (shutdown_tx, mut shutdown_rx) = tokio::sync::watch::channel(false);
             self.shutdown_tx = Some(shutdown_tx);
 
+            let (action_tx, mut action_rx) = tokio::sync::mpsc::unbounded_channel();
+            self.action_tx = Some(action_tx);
+
             self.rebuild_menu()?;
 
             loop {
@@ -218,6 +544,17 @@ impl SystemTray {
                     }
                 }
 
+                if state_rx.has_changed().unwrap_or(false) {
+                    let state = state_rx.borrow_and_update().clone();
+                    if let Err(e) = self.apply_state(state) {
+                        error!("Failed to apply tray state: {}", e);
+                    }
+                }
+
+                while let Ok(action) = action_rx.try_recv() {
+                    menu_handler(action);
+                }
+
                 while gtk::events_pending() {
                     gtk::main_iteration();
                 }
@@ -228,9 +565,12 @@ impl SystemTray {
 
         #[cfg(not(target_os = "linux"))]
         {
-            loop {
-                tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            let _ = &mut menu_handler;
+            while state_rx.changed().await.is_ok() {
+                let state = state_rx.borrow_and_update().clone();
+                self.apply_state(state)?;
             }
+            Ok(())
         }
     }
 
@@ -267,10 +607,11 @@ impl SimpleTray {
 
         for stream in &self.streams {
             info!(
-                "  {} - {} ({})",
+                "  {} - {} ({}){}",
                 stream.user_name,
                 stream.title,
-                stream.formatted_viewer_count()
+                stream.formatted_viewer_count(),
+                stream.followed_by_suffix()
             );
         }
         Ok(())
@@ -285,15 +626,197 @@ impl SimpleTray {
         self.streams.len()
     }
 
-    pub async fn run<F>(self, mut _menu_handler: F) -> Result<()>
+    pub async fn run<F>(
+        mut self,
+        mut state_rx: tokio::sync::watch::Receiver<TrayState>,
+        mut menu_handler: F,
+    ) -> Result<()>
     where
         F: FnMut(String) + Send + 'static,
     {
-        info!("Running simple tray (console mode)");
+        use tokio::signal::unix::{SignalKind, signal};
+
+        info!("Running simple tray (console mode), send SIGUSR1 to refresh");
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut refresh = signal(SignalKind::user_defined1())?;
 
         loop {
-            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
-            debug!("Tray running... {} streams", self.streams.len());
+            tokio::select! {
+                changed = state_rx.changed() => {
+                    if changed.is_err() {
+                        return Ok(());
+                    }
+                    let state = state_rx.borrow_and_update().clone();
+                    match &state.status {
+                        TrayStatus::Ok => {}
+                        TrayStatus::Refreshing => info!("Refreshing…"),
+                        TrayStatus::Offline { retry_in } => warn!("{}", offline_label(*retry_in)),
+                        TrayStatus::Error(message) => error!("Update failed: {}", message),
+                        TrayStatus::NeedsSignIn(accounts) => warn!(
+                            "{}, run with --add-account to sign in",
+                            needs_sign_in_label(accounts)
+                        ),
+                    }
+                    self.set_tooltip(&state.tooltip)?;
+                    if state.status != TrayStatus::Refreshing {
+                        self.update_streams(state.streams)?;
+                    }
+                }
+                _ = refresh.recv() => {
+                    info!("Manual refresh requested");
+                    menu_handler("refresh".to_string());
+                }
+                _ = terminate.recv() => {
+                    info!("Shutdown signal received, exiting");
+                    return Ok(());
+                }
+                _ = tokio::signal::ctrl_c() => {
+                    info!("Interrupted, exiting");
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+fn offline_label(retry_in: Duration) -> String {
+    format!("Offline – retrying in {}s", retry_in.as_secs())
+}
+
+// Splits streams into the pinned favourites section and the rest, both by viewers
+pub(crate) fn arrange_streams(
+    streams: &[Stream],
+    favourites: &[FavouriteChannel],
+    pin_favourites: bool,
+) -> (Vec<Stream>, Vec<Stream>) {
+    let mut sorted_streams = streams.to_vec();
+    sorted_streams.sort_by_key(|s| std::cmp::Reverse(s.viewer_count));
+
+    if !pin_favourites {
+        return (Vec::new(), sorted_streams);
+    }
+
+    sorted_streams.into_iter().partition(|stream| {
+        favourites
+            .iter()
+            .any(|channel| channel.broadcaster_id == stream.user_id)
+    })
+}
+
+// Groups keep the viewer ordering of their streams and are sorted by total viewers
+pub(crate) fn group_by_game(streams: Vec<Stream>) -> Vec<GameGroup> {
+    let mut groups: Vec<GameGroup> = Vec::new();
+
+    for stream in streams {
+        let game_name = if stream.game_name.is_empty() {
+            "No category".to_string()
+        } else {
+            stream.game_name.clone()
+        };
+
+        match groups.iter_mut().find(|g| g.game_name == game_name) {
+            Some(group) => {
+                group.total_viewers += u64::from(stream.viewer_count);
+                group.streams.push(stream);
+            }
+            None => groups.push(GameGroup {
+                game_name,
+                total_viewers: u64::from(stream.viewer_count),
+                streams: vec![stream],
+            }),
         }
     }
+
+    groups.sort_by_key(|g| std::cmp::Reverse(g.total_viewers));
+    groups
+}
+
+pub enum Tray {
+    System(SystemTray),
+    Simple(SimpleTray),
+}
+
+impl Tray {
+    pub async fn run<F>(
+        self,
+        state_rx: tokio::sync::watch::Receiver<TrayState>,
+        menu_handler: F,
+    ) -> Result<()>
+    where
+        F: FnMut(String) + Send + 'static,
+    {
+        match self {
+            Tray::System(tray) => tray.run(state_rx, menu_handler).await,
+            Tray::Simple(tray) => tray.run(state_rx, menu_handler).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::fake_twitch::stream;
+
+    fn favourite(id: &str, name: &str) -> FavouriteChannel {
+        FavouriteChannel {
+            broadcaster_id: id.to_string(),
+            broadcaster_name: name.to_string(),
+        }
+    }
+
+    fn playing(game: &str, mut stream: Stream) -> Stream {
+        stream.game_name = game.to_string();
+        stream
+    }
+
+    fn logins(streams: &[Stream]) -> Vec<&str> {
+        streams.iter().map(|s| s.user_login.as_str()).collect()
+    }
+
+    #[test]
+    fn favourites_are_pinned_above_the_rest() {
+        let streams = vec![
+            stream("s1", "u1", "alpha", 10),
+            stream("s2", "u2", "beta", 300),
+            stream("s3", "u3", "gamma", 50),
+            stream("s4", "u4", "delta", 20),
+        ];
+        let favourites = vec![favourite("u1", "alpha"), favourite("u4", "delta")];
+
+        let (pinned, others) = arrange_streams(&streams, &favourites, true);
+        assert_eq!(logins(&pinned), ["delta", "alpha"]);
+        assert_eq!(logins(&others), ["beta", "gamma"]);
+
+        let (pinned, others) = arrange_streams(&streams, &favourites, false);
+        assert!(pinned.is_empty());
+        assert_eq!(logins(&others), ["beta", "gamma", "delta", "alpha"]);
+    }
+
+    #[test]
+    fn streams_are_grouped_by_game_and_sorted_by_total_viewers() {
+        let streams = vec![
+            playing("Chess", stream("s1", "u1", "alpha", 300)),
+            playing("Just Chatting", stream("s2", "u2", "beta", 200)),
+            playing("Just Chatting", stream("s3", "u3", "gamma", 150)),
+            playing("", stream("s4", "u4", "delta", 5)),
+            playing("Chess", stream("s5", "u5", "epsilon", 10)),
+        ];
+        let (_, others) = arrange_streams(&streams, &[], false);
+
+        let groups = group_by_game(others);
+        let summary: Vec<(&str, u64, Vec<&str>)> = groups
+            .iter()
+            .map(|g| (g.game_name.as_str(), g.total_viewers, logins(&g.streams)))
+            .collect();
+
+        assert_eq!(
+            summary,
+            [
+                ("Just Chatting", 350, vec!["beta", "gamma"]),
+                ("Chess", 310, vec!["alpha", "epsilon"]),
+                ("No category", 5, vec!["delta"]),
+            ]
+        );
+    }
 }
@@ -0,0 +1,122 @@
+use anyhow::{Context, Result};
+use ring::digest::{SHA256, digest};
+use std::path::{Path, PathBuf};
+
+use crate::config::AppDirs;
+
+const ICON_PREFIX: &str = "twitch-indicator-live";
+
+const GLYPH: &str = r##"<path d="M10 4 L6 14 V52 H18 V60 H26 L34 52 H44 L58 38 V4 Z" fill="#9146FF"/>
+  <path d="M14 10 H52 V35 L43 44 H32 L24 52 V44 H14 Z" fill="#FFFFFF"/>
+  <rect x="26" y="18" width="5" height="14" fill="#9146FF"/>
+  <rect x="39" y="18" width="5" height="14" fill="#9146FF"/>"##;
+
+pub fn icon_dir(dirs: &AppDirs) -> PathBuf {
+    dirs.cache.join("icons")
+}
+
+// AppIndicator caches icons by name, so the name changes whenever the
+// rendering does and icons left by older versions are never reused
+pub fn icon_name(count: usize, svg: &str) -> String {
+    let hash: String = digest(&SHA256, svg.as_bytes()).as_ref()[..6]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("{ICON_PREFIX}-{}-{hash}", count.min(100))
+}
+
+pub fn write_badge_icon(dir: &Path, count: usize) -> Result<String> {
+    let svg = badge_svg(count);
+    let name = icon_name(count, &svg);
+    let path = dir.join(format!("{name}.svg"));
+
+    if !path.exists() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create icon directory: {dir:?}"))?;
+
+        // Renamed into place, so the tray never loads a half-written icon
+        let temp = dir.join(format!(".{name}.{}.tmp", std::process::id()));
+        std::fs::write(&temp, svg)
+            .with_context(|| format!("Failed to write tray icon: {temp:?}"))?;
+        std::fs::rename(&temp, &path)
+            .with_context(|| format!("Failed to write tray icon: {path:?}"))?;
+    }
+
+    Ok(name)
+}
+
+pub fn badge_svg(count: usize) -> String {
+    let badge = if count == 0 {
+        String::new()
+    } else {
+        let label = badge_label(count);
+        let font_size = match label.len() {
+            1 => 24,
+            2 => 19,
+            _ => 14,
+        };
+        format!(
+            r##"
+  <circle cx="45" cy="45" r="18" fill="#E91916" stroke="#FFFFFF" stroke-width="3"/>
+  <text x="45" y="45" dy="0.35em" text-anchor="middle" font-family="sans-serif" font-weight="bold" font-size="{font_size}" fill="#FFFFFF">{label}</text>"##
+        )
+    };
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="64" height="64" viewBox="0 0 64 64">
+  {GLYPH}{badge}
+</svg>
+"#
+    )
+}
+
+fn badge_label(count: usize) -> String {
+    if count > 99 {
+        "99+".to_string()
+    } else {
+        count.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn badge_shows_count_and_caps_large_numbers() {
+        assert!(!badge_svg(0).contains("<text"));
+        assert!(badge_svg(7).contains(">7</text>"));
+        assert!(badge_svg(150).contains(">99+</text>"));
+        assert!(icon_name(150, &badge_svg(150)).starts_with("twitch-indicator-live-100-"));
+    }
+
+    #[test]
+    fn icon_name_follows_rendered_content() {
+        let svg = badge_svg(3);
+
+        assert_eq!(icon_name(3, &svg), icon_name(3, &badge_svg(3)));
+        assert_ne!(icon_name(3, &svg), icon_name(3, "<svg/>"));
+    }
+
+    #[test]
+    fn icon_is_written_for_each_count() {
+        let dir = TempDir::new().unwrap();
+
+        let name = write_badge_icon(dir.path(), 3).unwrap();
+        assert!(name.starts_with("twitch-indicator-live-3-"));
+        assert_eq!(write_badge_icon(dir.path(), 3).unwrap(), name);
+
+        let path = dir.path().join(format!("{name}.svg"));
+        let svg = std::fs::read_to_string(&path).unwrap();
+        assert!(svg.contains(">3</text>"));
+
+        write_badge_icon(dir.path(), 4).unwrap();
+        let files: Vec<_> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|file| file.ends_with(".svg")));
+    }
+}
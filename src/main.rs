@@ -1,15 +1,19 @@
 mod api;
 mod config;
+mod dbus;
 mod gui;
+#[cfg(test)]
+mod testing;
 
 use anyhow::Result;
 use clap::Parser;
+use std::path::PathBuf;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing::{info, level_filters::LevelFilter};
+use tracing::{info, level_filters::LevelFilter, warn};
 use tracing_subscriber::{Layer, layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::config::Config;
+use crate::config::{AppDirs, Config, CredentialStore};
 use crate::gui::TwitchIndicator;
 
 #[derive(Parser)]
@@ -24,11 +28,27 @@ struct Args {
     #[arg(long, hide = true)]
     gtk_settings: bool,
 
+    /// Run without a system tray, logging live streams to the console
+    #[arg(long, alias = "headless")]
+    no_tray: bool,
+
     #[arg(long)]
     export_settings: Option<String>,
 
     #[arg(long)]
     import_settings: Option<String>,
+
+    /// Print authentication and API rate limit status, then exit
+    #[arg(long)]
+    diagnostics: bool,
+
+    /// Sign in to another Twitch account, or again to one whose sign-in expired
+    #[arg(long)]
+    add_account: bool,
+
+    /// Sign out of an account and delete its stored tokens
+    #[arg(long, value_name = "LOGIN")]
+    remove_account: Option<String>,
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -36,7 +56,7 @@ async fn main() -> Result<()> {
     let args = Args::parse();
 
     #[cfg(target_os = "linux")]
-    {
+    if !args.no_tray || args.gtk_settings {
         use gtk;
         if gtk::init().is_err() {
             eprintln!("Warning: Failed to initialize GTK. System tray may not work.");
@@ -60,35 +80,67 @@ async fn main() -> Result<()> {
 
     info!("Starting Twitch Indicator v{}", env!("CARGO_PKG_VERSION"));
 
+    let dirs = AppDirs::for_current_user()?;
+
     if args.gtk_settings {
-        let config = Config::load_or_create(args.config).await?;
+        let config = Config::load_or_create(args.config, &dirs).await?;
         let config_arc = Arc::new(RwLock::new(config));
 
-        let mut gtk_settings = crate::gui::gtk_settings::GtkSettingsWindow::new(config_arc).await?;
+        let mut gtk_settings =
+            crate::gui::gtk_settings::GtkSettingsWindow::new(config_arc, dirs).await?;
         gtk_settings.show_sync()?;
 
         return Ok(());
     }
 
-    let config = Config::load_or_create(args.config).await?;
+    let config_file = match &args.config {
+        Some(path) => PathBuf::from(path),
+        None => dirs.config_file(),
+    };
+    let credentials = CredentialStore::open_default(&dirs).await?;
+    if let Err(e) = credentials.migrate_legacy_tokens(&config_file).await {
+        warn!("Failed to move tokens out of the config file: {:#}", e);
+    }
+
+    let config = Config::load_or_create(args.config, &dirs).await?;
     let config = Arc::new(RwLock::new(config));
 
     if let Some(export_path) = args.export_settings {
-        let indicator = TwitchIndicator::new(config).await?;
+        let indicator = TwitchIndicator::new(config, credentials, dirs).await?;
         indicator.export_settings(&export_path).await?;
         println!("Settings exported to: {export_path}");
         return Ok(());
     }
 
     if let Some(import_path) = args.import_settings {
-        let indicator = TwitchIndicator::new(config).await?;
+        let indicator = TwitchIndicator::new(config, credentials, dirs).await?;
         indicator.import_settings(&import_path).await?;
         println!("Settings imported from: {import_path}");
         return Ok(());
     }
 
-    let indicator = TwitchIndicator::new(config).await?;
-    indicator.run().await?;
+    if args.diagnostics {
+        let indicator = TwitchIndicator::new(config, credentials, dirs).await?;
+        println!("{}", indicator.diagnostics().await?);
+        return Ok(());
+    }
+
+    if args.add_account {
+        let mut indicator = TwitchIndicator::new(config, credentials, dirs).await?;
+        let name = indicator.add_account().await?;
+        println!("Signed in: {name}");
+        return Ok(());
+    }
+
+    if let Some(account) = args.remove_account {
+        let mut indicator = TwitchIndicator::new(config, credentials, dirs).await?;
+        indicator.remove_account(&account).await?;
+        println!("Removed account: {account}");
+        return Ok(());
+    }
+
+    let indicator = TwitchIndicator::new(config, credentials, dirs).await?;
+    indicator.run(args.no_tray).await?;
 
     Ok(())
 }
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::object_server::SignalContext;
+use zbus::zvariant::{OwnedObjectPath, OwnedValue, Value};
+use zbus::{Connection, ObjectServer, connection, fdo, interface};
+
+use super::PrivateBus;
+
+const SERVICE_PATH: &str = "/org/freedesktop/secrets";
+const COLLECTION_PATH: &str = "/org/freedesktop/secrets/collection/login";
+const SESSION_PATH: &str = "/org/freedesktop/secrets/session/1";
+const PROMPT_PATH: &str = "/org/freedesktop/secrets/prompt";
+
+type Secret = (OwnedObjectPath, Vec<u8>, Vec<u8>, String);
+
+#[derive(Debug, Clone)]
+pub struct StoredItem {
+    pub path: String,
+    pub attributes: HashMap<String, String>,
+    pub value: Vec<u8>,
+    pub locked: bool,
+}
+
+// How the fake answers when an unlock prompt is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptReply {
+    Unlock,
+    Dismiss,
+    Ignore,
+}
+
+struct Keyring {
+    items: Vec<StoredItem>,
+    has_default_collection: bool,
+    prompt_reply: PromptReply,
+    prompts: usize,
+}
+
+type Shared = Arc<Mutex<Keyring>>;
+
+fn object_path(path: &str) -> OwnedObjectPath {
+    OwnedObjectPath::try_from(path.to_string()).unwrap()
+}
+
+fn failed(e: impl std::fmt::Display) -> fdo::Error {
+    fdo::Error::Failed(e.to_string())
+}
+
+struct Service {
+    keyring: Shared,
+}
+
+#[interface(name = "org.freedesktop.Secret.Service")]
+impl Service {
+    // Secrets cross the bus unencrypted, which the client asks for in tests
+    fn open_session(
+        &self,
+        algorithm: &str,
+        _input: OwnedValue,
+    ) -> fdo::Result<(OwnedValue, OwnedObjectPath)> {
+        if algorithm != "plain" {
+            return Err(fdo::Error::NotSupported(format!(
+                "Unsupported algorithm: {algorithm}"
+            )));
+        }
+
+        let output = Value::from("").try_to_owned().map_err(failed)?;
+        Ok((output, object_path(SESSION_PATH)))
+    }
+
+    fn search_items(
+        &self,
+        attributes: HashMap<String, String>,
+    ) -> (Vec<OwnedObjectPath>, Vec<OwnedObjectPath>) {
+        let keyring = self.keyring.lock().unwrap();
+        let (locked, unlocked): (Vec<&StoredItem>, Vec<&StoredItem>) = keyring
+            .items
+            .iter()
+            .filter(|item| {
+                attributes
+                    .iter()
+                    .all(|(key, value)| item.attributes.get(key) == Some(value))
+            })
+            .partition(|item| item.locked);
+
+        let paths = |items: Vec<&StoredItem>| {
+            items
+                .into_iter()
+                .map(|item| object_path(&item.path))
+                .collect()
+        };
+        (paths(unlocked), paths(locked))
+    }
+
+    async fn unlock(
+        &self,
+        objects: Vec<OwnedObjectPath>,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> fdo::Result<(Vec<OwnedObjectPath>, OwnedObjectPath)> {
+        let path = {
+            let mut keyring = self.keyring.lock().unwrap();
+            let any_locked = keyring
+                .items
+                .iter()
+                .any(|item| item.locked && objects.iter().any(|o| o.as_str() == item.path));
+            if !any_locked {
+                return Ok((objects, object_path("/")));
+            }
+
+            keyring.prompts += 1;
+            format!("{PROMPT_PATH}/{}", keyring.prompts)
+        };
+
+        let prompt = Prompt {
+            keyring: self.keyring.clone(),
+            objects,
+        };
+        server.at(path.as_str(), prompt).await?;
+        Ok((Vec::new(), object_path(&path)))
+    }
+
+    fn read_alias(&self, name: &str) -> OwnedObjectPath {
+        match name {
+            "default" if self.keyring.lock().unwrap().has_default_collection => {
+                object_path(COLLECTION_PATH)
+            }
+            _ => object_path("/"),
+        }
+    }
+}
+
+struct Prompt {
+    keyring: Shared,
+    objects: Vec<OwnedObjectPath>,
+}
+
+#[interface(name = "org.freedesktop.Secret.Prompt")]
+impl Prompt {
+    async fn prompt(
+        &self,
+        _window_id: &str,
+        #[zbus(signal_context)] ctxt: SignalContext<'_>,
+    ) -> fdo::Result<()> {
+        let reply = {
+            let mut keyring = self.keyring.lock().unwrap();
+            if keyring.prompt_reply == PromptReply::Unlock {
+                for item in keyring.items.iter_mut() {
+                    if self.objects.iter().any(|o| o.as_str() == item.path) {
+                        item.locked = false;
+                    }
+                }
+            }
+            keyring.prompt_reply
+        };
+
+        match reply {
+            PromptReply::Unlock => {
+                Self::completed(&ctxt, false, Value::from(self.objects.clone())).await?
+            }
+            PromptReply::Dismiss => {
+                Self::completed(&ctxt, true, Value::from(Vec::<OwnedObjectPath>::new())).await?
+            }
+            PromptReply::Ignore => {}
+        }
+        Ok(())
+    }
+
+    fn dismiss(&self) {}
+
+    #[zbus(signal)]
+    async fn completed(
+        ctxt: &SignalContext<'_>,
+        dismissed: bool,
+        result: Value<'_>,
+    ) -> zbus::Result<()>;
+}
+
+struct Collection {
+    keyring: Shared,
+    next_id: usize,
+}
+
+#[interface(name = "org.freedesktop.Secret.Collection")]
+impl Collection {
+    async fn create_item(
+        &mut self,
+        properties: HashMap<String, OwnedValue>,
+        secret: Secret,
+        replace: bool,
+        #[zbus(object_server)] server: &ObjectServer,
+    ) -> fdo::Result<(OwnedObjectPath, OwnedObjectPath)> {
+        let attributes = properties
+            .get("org.freedesktop.Secret.Item.Attributes")
+            .and_then(|value| value.try_clone().ok())
+            .and_then(|value| HashMap::<String, String>::try_from(value).ok())
+            .unwrap_or_default();
+        let (_, _, value, _) = secret;
+
+        {
+            let mut keyring = self.keyring.lock().unwrap();
+            if replace
+                && let Some(item) = keyring
+                    .items
+                    .iter_mut()
+                    .find(|item| item.attributes == attributes)
+            {
+                item.value = value;
+                return Ok((object_path(&item.path), object_path("/")));
+            }
+
+            self.next_id += 1;
+            keyring.items.push(StoredItem {
+                path: format!("{COLLECTION_PATH}/{}", self.next_id),
+                attributes,
+                value,
+                locked: false,
+            });
+        }
+
+        let path = format!("{COLLECTION_PATH}/{}", self.next_id);
+        let item = Item {
+            keyring: self.keyring.clone(),
+            path: path.clone(),
+        };
+        server.at(path.as_str(), item).await?;
+
+        Ok((object_path(&path), object_path("/")))
+    }
+}
+
+struct Item {
+    keyring: Shared,
+    path: String,
+}
+
+#[interface(name = "org.freedesktop.Secret.Item")]
+impl Item {
+    fn get_secret(&self, session: OwnedObjectPath) -> fdo::Result<Secret> {
+        let keyring = self.keyring.lock().unwrap();
+        let item = keyring
+            .items
+            .iter()
+            .find(|item| item.path == self.path)
+            .ok_or_else(|| fdo::Error::UnknownObject(self.path.clone()))?;
+        if item.locked {
+            return Err(failed("Item is locked"));
+        }
+
+        Ok((
+            session,
+            Vec::new(),
+            item.value.clone(),
+            "application/json".to_string(),
+        ))
+    }
+
+    #[zbus(property)]
+    fn locked(&self) -> bool {
+        self.keyring
+            .lock()
+            .unwrap()
+            .items
+            .iter()
+            .any(|item| item.path == self.path && item.locked)
+    }
+
+    fn delete(&self) -> OwnedObjectPath {
+        self.keyring
+            .lock()
+            .unwrap()
+            .items
+            .retain(|item| item.path != self.path);
+        object_path("/")
+    }
+}
+
+// Just enough of org.freedesktop.secrets to exercise the keyring backend
+pub struct FakeSecretService {
+    _connection: Connection,
+    keyring: Shared,
+}
+
+impl FakeSecretService {
+    pub async fn start(bus: &PrivateBus) -> Self {
+        let keyring = Arc::new(Mutex::new(Keyring {
+            items: Vec::new(),
+            has_default_collection: true,
+            prompt_reply: PromptReply::Unlock,
+            prompts: 0,
+        }));
+
+        let connection = connection::Builder::address(bus.address().as_str())
+            .unwrap()
+            .name("org.freedesktop.secrets")
+            .unwrap()
+            .serve_at(
+                SERVICE_PATH,
+                Service {
+                    keyring: keyring.clone(),
+                },
+            )
+            .unwrap()
+            .serve_at(
+                COLLECTION_PATH,
+                Collection {
+                    keyring: keyring.clone(),
+                    next_id: 0,
+                },
+            )
+            .unwrap()
+            .build()
+            .await
+            .expect("Failed to start fake Secret Service");
+
+        Self {
+            _connection: connection,
+            keyring,
+        }
+    }
+
+    pub fn items(&self) -> Vec<StoredItem> {
+        self.keyring.lock().unwrap().items.clone()
+    }
+
+    pub fn lock_all(&self) {
+        for item in self.keyring.lock().unwrap().items.iter_mut() {
+            item.locked = true;
+        }
+    }
+
+    pub fn set_prompt_reply(&self, reply: PromptReply) {
+        self.keyring.lock().unwrap().prompt_reply = reply;
+    }
+
+    pub fn remove_default_collection(&self) {
+        self.keyring.lock().unwrap().has_default_collection = false;
+    }
+}
@@ -0,0 +1,443 @@
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use tempfile::TempDir;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::api::models::{FollowedChannel, Stream, User};
+use crate::config::{AppDirs, Config, CredentialStore, DEFAULT_ACCOUNT, Tokens};
+
+pub const CLIENT_ID: &str = "fake-client-id";
+pub const ACCESS_TOKEN: &str = "fake-access-token";
+pub const REFRESH_TOKEN: &str = "fake-refresh-token";
+
+static CREDENTIAL_STORES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct FakeTwitchState {
+    pub users: Vec<User>,
+    pub streams: Vec<Stream>,
+    pub channels: Vec<FollowedChannel>,
+    pub access_token: String,
+    pub refresh_token: String,
+    pub refreshes: usize,
+    pub expires_in: u64,
+    pub page_size: usize,
+    pub page_overlap: usize,
+    // Hands out the same cursor forever, like a misbehaving API
+    pub repeat_cursor: bool,
+    pub requests: Vec<String>,
+    pub images: HashMap<String, Vec<u8>>,
+    pub rate_limit: u32,
+    pub rate_limit_remaining: u32,
+    pub throttled_requests: usize,
+    pub unavailable_requests: usize,
+    // Tokens of further signed-in users, mapped to their user ids
+    pub sessions: HashMap<String, String>,
+    // Channels each user follows; users without an entry follow every stream
+    pub follows: HashMap<String, Vec<String>>,
+}
+
+pub struct FakeTwitch {
+    server: Arc<Server>,
+    state: Arc<Mutex<FakeTwitchState>>,
+    base_url: String,
+    handle: Option<JoinHandle<()>>,
+    // Config saves and caches of the code under test land here instead of the home directory
+    user_dirs: TempDir,
+}
+
+impl FakeTwitch {
+    pub fn start() -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("Failed to bind fake Twitch"));
+        let port = server
+            .server_addr()
+            .to_ip()
+            .expect("Fake Twitch is not listening on IP")
+            .port();
+
+        let state = Arc::new(Mutex::new(FakeTwitchState {
+            users: vec![user("1000", "viewer")],
+            streams: Vec::new(),
+            channels: Vec::new(),
+            access_token: ACCESS_TOKEN.to_string(),
+            refresh_token: REFRESH_TOKEN.to_string(),
+            refreshes: 0,
+            expires_in: 3600,
+            page_size: 100,
+            page_overlap: 0,
+            repeat_cursor: false,
+            requests: Vec::new(),
+            images: HashMap::new(),
+            rate_limit: 800,
+            rate_limit_remaining: 800,
+            throttled_requests: 0,
+            unavailable_requests: 0,
+            sessions: HashMap::new(),
+            follows: HashMap::new(),
+        }));
+
+        let handle = {
+            let server = server.clone();
+            let state = state.clone();
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    Self::handle(&state, request);
+                }
+            })
+        };
+
+        Self {
+            server,
+            state,
+            base_url: format!("http://127.0.0.1:{port}"),
+            handle: Some(handle),
+            user_dirs: TempDir::new().expect("Failed to create temp dir"),
+        }
+    }
+
+    pub fn state(&self) -> MutexGuard<'_, FakeTwitchState> {
+        self.state.lock().unwrap()
+    }
+
+    pub fn image_url(&self, name: &str) -> String {
+        format!("{}/images/{name}", self.base_url)
+    }
+
+    pub fn config(&self) -> Config {
+        let mut config = Config::default();
+        config.twitch.client_id = CLIENT_ID.to_string();
+        config.twitch.api_base_url = format!("{}/helix", self.base_url);
+        config.twitch.oauth_base_url = format!("{}/oauth2", self.base_url);
+        config
+    }
+
+    pub fn dirs(&self) -> AppDirs {
+        AppDirs::under(self.user_dirs.path())
+    }
+
+    pub async fn credentials(&self) -> CredentialStore {
+        self.credentials_with(Tokens {
+            access_token: Some(ACCESS_TOKEN.to_string()),
+            refresh_token: None,
+        })
+        .await
+    }
+
+    // Every client gets its own encrypted store so tests never touch the desktop keyring
+    pub async fn credentials_with(&self, tokens: Tokens) -> CredentialStore {
+        let id = CREDENTIAL_STORES.fetch_add(1, Ordering::Relaxed);
+        let dir = self.user_dirs.path().join(format!("credentials-{id}"));
+
+        let store = CredentialStore::encrypted_file(
+            dir.join("credentials.enc"),
+            dir.join("credentials.key"),
+        );
+        store.save(DEFAULT_ACCOUNT, &tokens).await.unwrap();
+        store
+    }
+
+    fn handle(state: &Mutex<FakeTwitchState>, mut request: Request) {
+        let mut body = String::new();
+        let _ = request.as_reader().read_to_string(&mut body);
+        let form: HashMap<String, String> = url::form_urlencoded::parse(body.as_bytes())
+            .into_owned()
+            .collect();
+
+        let url = request.url().to_string();
+        let (path, query) = url.split_once('?').unwrap_or((&url, ""));
+        let params: Vec<(String, String)> = url::form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+        let headers: HashMap<String, String> = request
+            .headers()
+            .iter()
+            .map(|h| {
+                (
+                    h.field.as_str().as_str().to_ascii_lowercase(),
+                    h.value.to_string(),
+                )
+            })
+            .collect();
+
+        let mut state = state.lock().unwrap();
+        state.requests.push(url.clone());
+
+        if let Some(name) = path.strip_prefix("/images/") {
+            let response = Self::image(&state, name, headers.get("if-none-match"));
+            let _ = request.respond(response);
+            return;
+        }
+
+        let mut rate_limit_headers = Vec::new();
+        let (status, body) = match (request.method(), path) {
+            (Method::Get, "/oauth2/validate") => {
+                if headers.get("authorization") != Some(&format!("OAuth {}", state.access_token)) {
+                    Self::unauthorized()
+                } else {
+                    let user = &state.users[0];
+                    (
+                        200,
+                        json!({
+                            "client_id": CLIENT_ID,
+                            "login": user.login,
+                            "scopes": ["user:read:follows"],
+                            "user_id": user.id,
+                            "expires_in": state.expires_in,
+                        }),
+                    )
+                }
+            }
+            (Method::Post, "/oauth2/token") => Self::token(&mut state, &form),
+            (Method::Get, path) if path.starts_with("/helix/") => {
+                let viewer = headers
+                    .get("authorization")
+                    .and_then(|value| value.strip_prefix("Bearer "))
+                    .and_then(|token| Self::viewer(&state, token));
+
+                if viewer.is_none()
+                    || headers.get("client-id").map(String::as_str) != Some(CLIENT_ID)
+                {
+                    Self::unauthorized()
+                } else if state.unavailable_requests > 0 {
+                    state.unavailable_requests -= 1;
+                    (
+                        503,
+                        json!({ "error": "Service Unavailable", "status": 503 }),
+                    )
+                } else if state.throttled_requests > 0 {
+                    state.throttled_requests -= 1;
+                    rate_limit_headers = Self::rate_limit_headers(&state, 0);
+                    (429, json!({ "error": "Too Many Requests", "status": 429 }))
+                } else {
+                    state.rate_limit_remaining = state.rate_limit_remaining.saturating_sub(1);
+                    rate_limit_headers =
+                        Self::rate_limit_headers(&state, state.rate_limit_remaining);
+                    let viewer = viewer.unwrap_or_default();
+                    Self::helix(&state, &viewer, &path["/helix/".len()..], &params)
+                }
+            }
+            _ => (404, json!({ "error": "Not Found", "status": 404 })),
+        };
+
+        let mut response = Response::from_string(body.to_string())
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", "application/json").unwrap());
+        for header in rate_limit_headers {
+            response.add_header(header);
+        }
+        let _ = request.respond(response);
+    }
+
+    fn viewer(state: &FakeTwitchState, token: &str) -> Option<String> {
+        if token == state.access_token {
+            state.users.first().map(|user| user.id.clone())
+        } else {
+            state.sessions.get(token).cloned()
+        }
+    }
+
+    // The bucket refills by the time the reset passes, so tests never wait on it
+    fn rate_limit_headers(state: &FakeTwitchState, remaining: u32) -> Vec<Header> {
+        let reset = chrono::Utc::now().timestamp().to_string();
+        vec![
+            Header::from_bytes("Ratelimit-Limit", state.rate_limit.to_string()).unwrap(),
+            Header::from_bytes("Ratelimit-Remaining", remaining.to_string()).unwrap(),
+            Header::from_bytes("Ratelimit-Reset", reset).unwrap(),
+        ]
+    }
+
+    fn image(
+        state: &FakeTwitchState,
+        name: &str,
+        if_none_match: Option<&String>,
+    ) -> Response<std::io::Cursor<Vec<u8>>> {
+        let Some(bytes) = state.images.get(name) else {
+            return Response::from_data(Vec::new()).with_status_code(404);
+        };
+
+        let etag = format!(
+            "\"{}-{}\"",
+            bytes.len(),
+            bytes.iter().map(|b| *b as u64).sum::<u64>()
+        );
+        if if_none_match == Some(&etag) {
+            return Response::from_data(Vec::new()).with_status_code(304);
+        }
+
+        Response::from_data(bytes.clone())
+            .with_header(Header::from_bytes("Content-Type", "image/png").unwrap())
+            .with_header(Header::from_bytes("ETag", etag.as_bytes()).unwrap())
+    }
+
+    fn token(
+        state: &mut FakeTwitchState,
+        form: &HashMap<String, String>,
+    ) -> (u16, serde_json::Value) {
+        let valid = form.get("grant_type").map(String::as_str) == Some("refresh_token")
+            && form.get("client_id").map(String::as_str) == Some(CLIENT_ID)
+            && form.get("refresh_token") == Some(&state.refresh_token);
+
+        if !valid {
+            return (
+                400,
+                json!({ "status": 400, "message": "Invalid refresh token" }),
+            );
+        }
+
+        state.refreshes += 1;
+        state.access_token = format!("refreshed-access-token-{}", state.refreshes);
+        state.refresh_token = format!("refreshed-refresh-token-{}", state.refreshes);
+        state.expires_in = 14400;
+
+        (
+            200,
+            json!({
+                "access_token": state.access_token,
+                "refresh_token": state.refresh_token,
+                "expires_in": state.expires_in,
+                "scope": ["user:read:follows"],
+                "token_type": "bearer",
+            }),
+        )
+    }
+
+    fn helix(
+        state: &FakeTwitchState,
+        viewer: &str,
+        endpoint: &str,
+        params: &[(String, String)],
+    ) -> (u16, serde_json::Value) {
+        let param = |name: &str| {
+            params
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        match endpoint {
+            "users" => {
+                let values = |name: &str| -> Vec<&str> {
+                    params
+                        .iter()
+                        .filter(|(key, _)| key == name)
+                        .map(|(_, value)| value.as_str())
+                        .collect()
+                };
+                let (ids, logins) = (values("id"), values("login"));
+                let users: Vec<&User> = if ids.is_empty() && logins.is_empty() {
+                    state.users.iter().filter(|u| u.id == viewer).collect()
+                } else {
+                    state
+                        .users
+                        .iter()
+                        .filter(|u| {
+                            ids.contains(&u.id.as_str()) || logins.contains(&u.login.as_str())
+                        })
+                        .collect()
+                };
+                (200, json!({ "data": users }))
+            }
+            "streams/followed" => {
+                let streams: Vec<&Stream> = match state.follows.get(viewer) {
+                    Some(follows) => state
+                        .streams
+                        .iter()
+                        .filter(|s| follows.contains(&s.user_id))
+                        .collect(),
+                    None => state.streams.iter().collect(),
+                };
+                Self::page(state, &streams, param("first"), param("after"))
+            }
+            "channels/followed" => {
+                Self::page(state, &state.channels, param("first"), param("after"))
+            }
+            _ => (404, json!({ "error": "Not Found", "status": 404 })),
+        }
+    }
+
+    fn page<T: serde::Serialize>(
+        state: &FakeTwitchState,
+        items: &[T],
+        first: Option<&str>,
+        after: Option<&str>,
+    ) -> (u16, serde_json::Value) {
+        let first = first
+            .and_then(|f| f.parse::<usize>().ok())
+            .unwrap_or(20)
+            .min(state.page_size)
+            .max(1);
+        let offset = after.and_then(|a| a.parse::<usize>().ok()).unwrap_or(0);
+        let end = (offset + first).min(items.len());
+        let data = &items[offset.min(end)..end];
+
+        // Overlapping pages mimic streams shifting position between requests
+        let pagination = if state.repeat_cursor {
+            json!({ "cursor": "repeated" })
+        } else if end < items.len() {
+            json!({ "cursor": (end - state.page_overlap.min(first - 1)).to_string() })
+        } else {
+            json!({})
+        };
+
+        (200, json!({ "data": data, "pagination": pagination }))
+    }
+
+    fn unauthorized() -> (u16, serde_json::Value) {
+        (
+            401,
+            json!({ "error": "Unauthorized", "status": 401, "message": "Invalid OAuth token" }),
+        )
+    }
+}
+
+impl Drop for FakeTwitch {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+pub fn user(id: &str, login: &str) -> User {
+    User {
+        id: id.to_string(),
+        login: login.to_string(),
+        display_name: login.to_string(),
+        profile_image_url: format!("https://example.invalid/{login}-profile_image-300x300.png"),
+        view_count: 0,
+        created_at: "2020-01-01T00:00:00Z".to_string(),
+    }
+}
+
+pub fn stream(id: &str, user_id: &str, login: &str, viewer_count: u32) -> Stream {
+    Stream {
+        id: id.to_string(),
+        user_id: user_id.to_string(),
+        user_login: login.to_string(),
+        user_name: login.to_string(),
+        game_id: "509658".to_string(),
+        game_name: "Just Chatting".to_string(),
+        title: format!("{login} is streaming"),
+        viewer_count,
+        started_at: "2024-01-01T12:00:00Z".parse().unwrap(),
+        language: "en".to_string(),
+        thumbnail_url: format!(
+            "https://example.invalid/live_user_{login}-{{width}}x{{height}}.jpg"
+        ),
+        tag_ids: Vec::new(),
+        is_mature: false,
+        followed_by: Vec::new(),
+    }
+}
+
+pub fn channel(id: &str, login: &str) -> FollowedChannel {
+    FollowedChannel {
+        broadcaster_id: id.to_string(),
+        broadcaster_login: login.to_string(),
+        broadcaster_name: login.to_string(),
+        followed_at: "2022-01-01T00:00:00Z".to_string(),
+    }
+}
@@ -0,0 +1,9 @@
+pub mod fake_secret_service;
+pub mod fake_twitch;
+pub mod private_bus;
+pub mod recording_notifier;
+
+pub use fake_secret_service::FakeSecretService;
+pub use fake_twitch::FakeTwitch;
+pub use private_bus::PrivateBus;
+pub use recording_notifier::RecordingNotifier;
@@ -0,0 +1,87 @@
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
+use tokio::sync::{Mutex, MutexGuard};
+
+const BUS_CONFIG: &str = r#"<!DOCTYPE busconfig PUBLIC "-//freedesktop//DTD D-Bus Bus Configuration 1.0//EN"
+ "http://www.freedesktop.org/standards/dbus/1.0/busconfig.dtd">
+<busconfig>
+  <type>session</type>
+  <listen>unix:path=SOCKET_PATH</listen>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>
+"#;
+
+// Held while a test points the session bus at its own private bus
+static SESSION_BUS: Mutex<()> = Mutex::const_new(());
+
+pub struct PrivateBus {
+    daemon: Child,
+    socket: PathBuf,
+    _dir: TempDir,
+}
+
+impl PrivateBus {
+    // Bus tests are #[ignore]d by default, so this fails loudly instead of skipping
+    pub fn start() -> Self {
+        let dir = TempDir::new().expect("Failed to create bus directory");
+        let socket = dir.path().join("bus");
+        let config = dir.path().join("bus.conf");
+        std::fs::write(
+            &config,
+            BUS_CONFIG.replace("SOCKET_PATH", &socket.to_string_lossy()),
+        )
+        .expect("Failed to write bus config");
+
+        let mut daemon = Command::new("dbus-daemon")
+            .arg(format!("--config-file={}", config.display()))
+            .arg("--nofork")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("dbus-daemon is needed for bus tests");
+
+        // The socket file appears before the daemon listens on it, so wait for a connection
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while UnixStream::connect(&socket).is_err() {
+            if Instant::now() > deadline {
+                let _ = daemon.kill();
+                let _ = daemon.wait();
+                panic!("dbus-daemon did not accept connections on {socket:?}");
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        Self {
+            daemon,
+            socket,
+            _dir: dir,
+        }
+    }
+
+    pub fn address(&self) -> String {
+        format!("unix:path={}", self.socket.display())
+    }
+
+    // For clients that only connect to the session bus; keep the guard until they have
+    pub async fn set_as_session_bus(&self) -> MutexGuard<'static, ()> {
+        let guard = SESSION_BUS.lock().await;
+        // SAFETY: std serializes its own environment access, and nothing in the tests
+        // reads the environment through libc
+        unsafe { std::env::set_var("DBUS_SESSION_BUS_ADDRESS", self.address()) };
+        guard
+    }
+}
+
+impl Drop for PrivateBus {
+    fn drop(&mut self) {
+        let _ = self.daemon.kill();
+        let _ = self.daemon.wait();
+    }
+}
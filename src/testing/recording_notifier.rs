@@ -0,0 +1,55 @@
+use anyhow::Result;
+use notify_rust::Notification;
+use std::sync::{Arc, Mutex};
+
+use crate::gui::notifications::{ActionHandler, Notifier};
+
+struct Recorded {
+    notifications: Vec<Notification>,
+    handlers: Vec<ActionHandler>,
+}
+
+// Keeps notifications instead of showing them; clones share what was recorded
+#[derive(Clone)]
+pub struct RecordingNotifier {
+    recorded: Arc<Mutex<Recorded>>,
+}
+
+impl RecordingNotifier {
+    pub fn new() -> Self {
+        Self {
+            recorded: Arc::new(Mutex::new(Recorded {
+                notifications: Vec::new(),
+                handlers: Vec::new(),
+            })),
+        }
+    }
+
+    // Drains the notifications shown so far
+    pub fn take(&self) -> Vec<Notification> {
+        std::mem::take(&mut self.recorded.lock().unwrap().notifications)
+    }
+
+    pub fn summaries(&self) -> Vec<String> {
+        self.take().into_iter().map(|n| n.summary).collect()
+    }
+
+    // Acts on the oldest notification still waiting, as a click on it would
+    pub fn invoke(&self, action: &str) {
+        let handler = self.recorded.lock().unwrap().handlers.remove(0);
+        handler(action);
+    }
+}
+
+impl Notifier for RecordingNotifier {
+    fn supports_images(&mut self) -> bool {
+        true
+    }
+
+    fn show(&mut self, notification: Notification, on_action: ActionHandler) -> Result<()> {
+        let mut recorded = self.recorded.lock().unwrap();
+        recorded.notifications.push(notification);
+        recorded.handlers.push(on_action);
+        Ok(())
+    }
+}